@@ -0,0 +1,519 @@
+use std::fmt::Write;
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 500.0;
+const LEFT: f64 = 90.0;
+const RIGHT: f64 = 200.0;
+const TOP: f64 = 50.0;
+const BOTTOM: f64 = 60.0;
+const PLOT_WIDTH: f64 = WIDTH - LEFT - RIGHT;
+const PLOT_HEIGHT: f64 = HEIGHT - TOP - BOTTOM;
+
+//Histograms of more series than this get a panel each instead of being
+//drawn over each other.
+const MAX_OVERLAID: usize = 3;
+const PANEL_HEIGHT: f64 = 100.0;
+const PANEL_GAP: f64 = 15.0;
+
+const COLORS: [&str; 10] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+    "#7f7f7f", "#bcbd22",
+];
+
+//Lines after the first round of colors are dashed, so no two series of a
+//chart look the same until there are 30 of them.
+const DASHES: [&str; 3] = ["", "8,4", "2,3"];
+
+#[derive(Clone, Copy)]
+enum Marker {
+    Circle,
+    Square,
+    Triangle,
+    Diamond,
+}
+
+const MARKERS: [Marker; 4] = [Marker::Circle, Marker::Square, Marker::Triangle, Marker::Diamond];
+
+struct LineStyle {
+    color: &'static str,
+    dash: &'static str,
+    marker: Marker,
+}
+
+impl LineStyle {
+    fn new(i: usize) -> Self {
+        Self {
+            color: COLORS[i % COLORS.len()],
+            dash: DASHES[(i / COLORS.len()) % DASHES.len()],
+            marker: MARKERS[i % MARKERS.len()],
+        }
+    }
+
+    fn line(&self, svg: &mut String, points: &[(f64, f64)]) {
+        let path: Vec<String> = points.iter().map(|(x, y)| format!("{:.1},{:.1}", x, y)).collect();
+        let dash = if self.dash.is_empty() {
+            String::new()
+        } else {
+            format!(" stroke-dasharray=\"{}\"", self.dash)
+        };
+
+        writeln!(
+            svg,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"{}/>",
+            path.join(" "),
+            self.color,
+            dash,
+        ).unwrap();
+    }
+
+    fn marker(&self, svg: &mut String, x: f64, y: f64) {
+        let r = 3.5;
+
+        match self.marker {
+            Marker::Circle => writeln!(
+                svg,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"{}\"/>",
+                x, y, self.color,
+            ),
+            Marker::Square => writeln!(
+                svg,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"6\" height=\"6\" fill=\"{}\"/>",
+                x - 3.0, y - 3.0, self.color,
+            ),
+            Marker::Triangle => writeln!(
+                svg,
+                "<polygon points=\"{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}\" fill=\"{}\"/>",
+                x, y - r, x + r, y + r * 0.8, x - r, y + r * 0.8, self.color,
+            ),
+            Marker::Diamond => writeln!(
+                svg,
+                "<polygon points=\"{:.1},{:.1} {:.1},{:.1} {:.1},{:.1} {:.1},{:.1}\" fill=\"{}\"/>",
+                x, y - r, x + r, y, x, y + r, x - r, y, self.color,
+            ),
+        }.unwrap();
+    }
+}
+
+//Where a plot is drawn on the chart, as the top and the height of its frame.
+#[derive(Clone, Copy)]
+struct Frame {
+    top: f64,
+    height: f64,
+}
+
+impl Frame {
+    fn bottom(&self) -> f64 {
+        self.top + self.height
+    }
+}
+
+const FRAME: Frame = Frame { top: TOP, height: PLOT_HEIGHT };
+
+//Charts are written as standalone SVG documents, so they can be opened in a
+//browser without any plotting program.
+pub struct Series {
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+//One line per series on log-log axes, such as costs against array length.
+//Points that are not positive cannot be drawn and are left out.
+pub fn log_log_chart(title: &str, x_label: &str, y_label: &str, series: &[Series]) -> String {
+    let points = || {
+        series
+            .iter()
+            .flat_map(|series| series.points.iter())
+            .filter(|&&(x, y)| x > 0.0 && y > 0.0)
+    };
+
+    let (x_low, x_high) = decades(points().map(|&(x, _)| x));
+    let (y_low, y_high) = decades(points().map(|&(_, y)| y));
+    let scale_x = |x: f64| LEFT + (x.log10() - x_low) / (x_high - x_low) * PLOT_WIDTH;
+    let scale_y = |y: f64| TOP + PLOT_HEIGHT - (y.log10() - y_low) / (y_high - y_low) * PLOT_HEIGHT;
+
+    let mut svg = begin(title, x_label, y_label, HEIGHT);
+    frame(&mut svg, FRAME);
+
+    for decade in x_low as i32..=x_high as i32 {
+        let value = 10f64.powi(decade);
+        vertical_tick(&mut svg, FRAME, scale_x(value), Some(&tick_label(value)));
+    }
+
+    for decade in y_low as i32..=y_high as i32 {
+        let value = 10f64.powi(decade);
+        horizontal_tick(&mut svg, scale_y(value), &tick_label(value));
+    }
+
+    for (i, series) in series.iter().enumerate() {
+        let style = LineStyle::new(i);
+        let points: Vec<(f64, f64)> = series.points
+            .iter()
+            .filter(|&&(x, y)| x > 0.0 && y > 0.0)
+            .map(|&(x, y)| (scale_x(x), scale_y(y)))
+            .collect();
+
+        style.line(&mut svg, &points);
+        for &(x, y) in points.iter() {
+            style.marker(&mut svg, x, y);
+        }
+
+        line_legend(&mut svg, TOP + 10.0 + i as f64 * 20.0, &series.name, &style);
+    }
+
+    end(svg)
+}
+
+//Histograms of the values of each series, with the same bins for all of
+//them, such as the comparisons of every round. A few series are drawn over
+//each other, and more get a panel each with the same axes.
+pub fn histogram_chart(
+    title: &str,
+    x_label: &str,
+    series: &[(String, &[u64])],
+    bins: usize,
+) -> String {
+    let values = series.iter().flat_map(|(_, values)| values.iter().copied());
+    let (start, width, count) = bin_range(values, bins);
+
+    let counts: Vec<Vec<u64>> = series
+        .iter()
+        .map(|(_, values)| {
+            let mut counts = vec![0; count];
+            for &value in values.iter() {
+                counts[((value - start) / width) as usize] += 1;
+            }
+            counts
+        })
+        .collect();
+
+    let frames: Vec<Frame> = if series.len() <= MAX_OVERLAID {
+        vec![FRAME]
+    } else {
+        (0..series.len())
+            .map(|i| Frame {
+                top: TOP + i as f64 * (PANEL_HEIGHT + PANEL_GAP),
+                height: PANEL_HEIGHT,
+            })
+            .collect()
+    };
+    let last = frames[frames.len() - 1];
+
+    let highest = counts.iter().flatten().copied().max().unwrap_or(0).max(1) as f64;
+    let y_step = nice_step(highest);
+    let y_high = (highest / y_step).ceil() * y_step;
+    let x_low = start as f64;
+    let x_high = (start + width * count as u64) as f64;
+
+    let scale_x = |x: f64| LEFT + (x - x_low) / (x_high - x_low) * PLOT_WIDTH;
+    let scale_y = |frame: Frame, y: f64| frame.bottom() - y / y_high * frame.height;
+
+    let mut svg = begin(title, x_label, "Rounds", last.bottom() + BOTTOM);
+
+    //Panels only label the x axis once, under the last of them.
+    let x_step = nice_step(x_high - x_low);
+    for &panel in frames.iter() {
+        frame(&mut svg, panel);
+
+        let mut tick = (x_low / x_step).ceil() * x_step;
+        while tick <= x_high {
+            let label = if panel.top == last.top { Some(tick_label(tick)) } else { None };
+            vertical_tick(&mut svg, panel, scale_x(tick), label.as_deref());
+            tick += x_step;
+        }
+
+        //Panels are too short for more than the bottom and the top.
+        let y_step = if frames.len() > 1 { y_high } else { y_step };
+        let mut tick = 0.0;
+        while tick <= y_high {
+            horizontal_tick(&mut svg, scale_y(panel, tick), &tick_label(tick));
+            tick += y_step;
+        }
+    }
+
+    for (i, ((name, _), counts)) in series.iter().zip(counts.iter()).enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        let frame = frames[i % frames.len()];
+
+        for (bin, &rounds) in counts.iter().enumerate() {
+            if rounds == 0 {
+                continue;
+            }
+
+            let left = scale_x((start + width * bin as u64) as f64);
+            let right = scale_x((start + width * (bin as u64 + 1)) as f64);
+            let top = scale_y(frame, rounds as f64);
+
+            writeln!(
+                svg,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\" \
+                fill-opacity=\"0.5\" stroke=\"{}\"/>",
+                left,
+                top,
+                right - left,
+                frame.bottom() - top,
+                color,
+                color,
+            ).unwrap();
+        }
+
+        let y = if frames.len() > 1 {
+            frame.top + 10.0
+        } else {
+            TOP + 10.0 + i as f64 * 20.0
+        };
+        legend(&mut svg, y, name, color);
+    }
+
+    end(svg)
+}
+
+//The first bin, the width of every bin and the number of bins needed to
+//cover the values with at most the given number of whole-number bins.
+fn bin_range(values: impl Iterator<Item = u64> + Clone, bins: usize) -> (u64, u64, usize) {
+    let low = values.clone().min().unwrap_or(0);
+    let high = values.max().unwrap_or(0);
+
+    let span = high - low + 1;
+    let width = span.div_ceil(bins.max(1) as u64);
+    let count = span.div_ceil(width) as usize;
+
+    (low, width, count)
+}
+
+//The whole powers of 10 enclosing the values, spanning at least one decade.
+fn decades(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (low, high) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), value| {
+        (low.min(value.log10()), high.max(value.log10()))
+    });
+
+    if !low.is_finite() || !high.is_finite() {
+        return (0.0, 1.0);
+    }
+
+    let (low, high) = (low.floor(), high.ceil());
+    if low == high {
+        (low, high + 1.0)
+    } else {
+        (low, high)
+    }
+}
+
+//A step of 1, 2 or 5 times a power of 10 giving about 5 ticks over the range.
+fn nice_step(range: f64) -> f64 {
+    if range <= 0.0 {
+        return 1.0;
+    }
+
+    let raw = range / 5.0;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = match raw / magnitude {
+        r if r < 1.5 => 1.0,
+        r if r < 3.0 => 2.0,
+        r if r < 7.0 => 5.0,
+        _ => 10.0,
+    } * magnitude;
+
+    step.max(1.0)
+}
+
+fn tick_label(value: f64) -> String {
+    if value >= 1e6 {
+        format!("{:e}", value)
+    } else {
+        format!("{}", value)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+//The title and the axis labels of a chart of the given height. Frames are
+//drawn separately, since a chart can have several.
+fn begin(title: &str, x_label: &str, y_label: &str, height: f64) -> String {
+    let mut svg = String::new();
+
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+        viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"12\">",
+        w = WIDTH,
+        h = height,
+    ).unwrap();
+    writeln!(svg, "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>", WIDTH, height).unwrap();
+    writeln!(
+        svg,
+        "<text x=\"{:.1}\" y=\"25\" text-anchor=\"middle\" font-size=\"16\">{}</text>",
+        LEFT + PLOT_WIDTH / 2.0,
+        escape(title),
+    ).unwrap();
+    writeln!(
+        svg,
+        "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+        LEFT + PLOT_WIDTH / 2.0,
+        height - 15.0,
+        escape(x_label),
+    ).unwrap();
+    writeln!(
+        svg,
+        "<text x=\"20\" y=\"{y:.1}\" text-anchor=\"middle\" transform=\"rotate(-90 20 {y:.1})\">{}</text>",
+        escape(y_label),
+        y = (TOP + height - BOTTOM) / 2.0,
+    ).unwrap();
+
+    svg
+}
+
+fn frame(svg: &mut String, frame: Frame) {
+    writeln!(
+        svg,
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"black\"/>",
+        LEFT, frame.top, PLOT_WIDTH, frame.height,
+    ).unwrap();
+}
+
+fn end(mut svg: String) -> String {
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn vertical_tick(svg: &mut String, frame: Frame, x: f64, label: Option<&str>) {
+    writeln!(
+        svg,
+        "<line x1=\"{x:.1}\" y1=\"{}\" x2=\"{x:.1}\" y2=\"{}\" stroke=\"#dddddd\"/>",
+        frame.top,
+        frame.bottom(),
+    ).unwrap();
+
+    if let Some(label) = label {
+        writeln!(
+            svg,
+            "<text x=\"{x:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            frame.bottom() + 18.0,
+            escape(label),
+        ).unwrap();
+    }
+}
+
+fn horizontal_tick(svg: &mut String, y: f64, label: &str) {
+    writeln!(
+        svg,
+        "<line x1=\"{}\" y1=\"{y:.1}\" x2=\"{}\" y2=\"{y:.1}\" stroke=\"#dddddd\"/>",
+        LEFT,
+        LEFT + PLOT_WIDTH,
+    ).unwrap();
+    writeln!(
+        svg,
+        "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+        LEFT - 6.0,
+        y + 4.0,
+        escape(label),
+    ).unwrap();
+}
+
+fn legend(svg: &mut String, y: f64, name: &str, color: &str) {
+    let x = LEFT + PLOT_WIDTH + 15.0;
+
+    writeln!(
+        svg,
+        "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"12\" height=\"12\" fill=\"{}\"/>",
+        x,
+        y,
+        color,
+    ).unwrap();
+    writeln!(svg, "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>", x + 18.0, y + 10.0, escape(name))
+        .unwrap();
+}
+
+//A short piece of the line with its marker, so dashes and markers tell
+//series of the same color apart.
+fn line_legend(svg: &mut String, y: f64, name: &str, style: &LineStyle) {
+    let x = LEFT + PLOT_WIDTH + 15.0;
+
+    style.line(svg, &[(x, y + 6.0), (x + 24.0, y + 6.0)]);
+    style.marker(svg, x + 12.0, y + 6.0);
+    writeln!(svg, "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>", x + 30.0, y + 10.0, escape(name))
+        .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_log_chart() {
+        let svg = log_log_chart("Costs", "Length", "Comparisons", &[
+            Series {
+                name: "a<b".to_string(),
+                points: vec![(100.0, 700.0), (1000.0, 13000.0), (0.0, 5.0)],
+            },
+            Series {
+                name: "c".to_string(),
+                points: vec![(100.0, 900.0), (1000.0, 15000.0)],
+            },
+        ]);
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<polyline").count(), 4, "a line per series and legend");
+        assert_eq!(svg.matches("<circle").count(), 3, "the point at 0 is left out");
+        assert_eq!(svg.matches("width=\"6\"").count(), 3, "squares for the second series");
+        assert!(svg.contains(">a&lt;b</text>"), "names are escaped");
+        assert!(svg.contains(">10000</text>") && svg.contains(">100</text>"), "decade ticks");
+    }
+
+    #[test]
+    fn test_bin_range() {
+        assert_eq!(bin_range([10, 12, 19].into_iter(), 5), (10, 2, 5));
+        assert_eq!(bin_range([3, 5].into_iter(), 20), (3, 1, 3), "bins are whole numbers");
+        assert_eq!(bin_range([7].into_iter(), 10), (7, 1, 1));
+    }
+
+    #[test]
+    fn test_histogram_chart() {
+        let a = [10, 11, 11, 12, 20];
+        let b = [15, 15];
+        let svg = histogram_chart("Rounds", "Comparisons", &[
+            ("a".to_string(), &a[..]),
+            ("b".to_string(), &b[..]),
+        ], 11);
+
+        //Bins of width 1 from 10 to 20, of which 4 are used by a and 1 by b.
+        assert_eq!(svg.matches("fill-opacity").count(), 5);
+        assert_eq!(nice_step(100.0), 20.0);
+        assert_eq!(nice_step(7.0), 1.0);
+        assert!(svg.contains("height=\"500\""), "overlaid in one frame");
+    }
+
+    #[test]
+    fn test_line_styles_differ() {
+        let styles: Vec<(&str, &str)> = (0..30)
+            .map(LineStyle::new)
+            .map(|style| (style.color, style.dash))
+            .collect();
+
+        for (i, style) in styles.iter().enumerate() {
+            assert!(!styles[..i].contains(style), "style {} is already used", i);
+        }
+    }
+
+    #[test]
+    fn test_histogram_panels() {
+        let values: Vec<Vec<u64>> = (0..13).map(|i| vec![10 + i, 20 + i]).collect();
+        let series: Vec<(String, &[u64])> = values
+            .iter()
+            .enumerate()
+            .map(|(i, values)| (i.to_string(), &values[..]))
+            .collect();
+
+        let svg = histogram_chart("Rounds", "Comparisons", &series, 20);
+
+        assert_eq!(svg.matches("stroke=\"black\"").count(), 13, "a panel per series");
+        assert_eq!(svg.matches("fill-opacity").count(), 26);
+        let height = TOP + 13.0 * PANEL_HEIGHT + 12.0 * PANEL_GAP + BOTTOM;
+        assert!(svg.contains(&format!("height=\"{}\"", height)), "taller for the panels");
+    }
+}
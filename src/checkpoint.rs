@@ -0,0 +1,209 @@
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::sort::SorterResults;
+
+const HEADER: &str = "shellsort checkpoint 1";
+
+pub struct CheckpointFile {
+    pub path: PathBuf,
+    pub resume: bool,
+}
+
+impl CheckpointFile {
+    //Only returns a checkpoint when resuming was asked for and one exists,
+    //so the same command line can be used to start and to resume a run.
+    pub fn load_for_resume(&self) -> Result<Option<Checkpoint>, String> {
+        if !self.resume || !self.path.exists() {
+            return Ok(None);
+        }
+
+        Checkpoint::load(&self.path).map(Some)
+    }
+}
+
+//A checkpoint is a plain text file with one `key value` entry per line.
+pub struct Checkpoint {
+    entries: Vec<(String, String)>,
+}
+
+impl Checkpoint {
+    pub fn new(mode: &str) -> Self {
+        let mut checkpoint = Self {
+            entries: Vec::new(),
+        };
+
+        checkpoint.set("mode", mode);
+        checkpoint
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Display) {
+        let value = value.to_string();
+
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.entries.push((key.to_string(), value)),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Result<&str, String> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .ok_or(format!("Checkpoint is missing '{}'.", key))
+    }
+
+    pub fn parse<T: FromStr>(&self, key: &str) -> Result<T, String> {
+        self.get(key)?
+            .parse::<T>()
+            .map_err(|_| format!("Checkpoint has an invalid '{}'.", key))
+    }
+
+    //Fails when the checkpoint was written by a run with different settings,
+    //since resuming it would not reproduce that run.
+    pub fn expect(&self, key: &str, value: impl Display) -> Result<(), String> {
+        let value = value.to_string();
+        let found = self.get(key)?;
+
+        if found != value {
+            return Err(format!(
+                "Checkpoint was written with {} '{}', not '{}'.",
+                key, found, value,
+            ));
+        }
+
+        Ok(())
+    }
+
+    //Every metric of the results is an entry of its own, named `key.metric`.
+    pub fn set_results(&mut self, key: &str, results: &SorterResults) {
+        for (metric, accumulator) in results.metrics.iter().zip(results.accumulators.iter()) {
+            self.set(&format!("{}.{}", key, metric.replace(' ', "_")), accumulator);
+        }
+    }
+
+    pub fn load_results(&self, key: &str, results: &mut SorterResults) -> Result<(), String> {
+        for (metric, accumulator) in results.metrics.iter().zip(results.accumulators.iter_mut()) {
+            *accumulator = self.parse(&format!("{}.{}", key, metric.replace(' ', "_")))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut contents = String::from(HEADER);
+        contents.push('\n');
+
+        for (key, value) in self.entries.iter() {
+            contents.push_str(key);
+            contents.push(' ');
+            contents.push_str(value);
+            contents.push('\n');
+        }
+
+        //Write next to the target and rename, so an interruption while
+        //saving never leaves a truncated checkpoint behind.
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        fs::write(&temporary, contents)
+            .and_then(|_| fs::rename(&temporary, path))
+            .map_err(|e| format!("Could not write checkpoint: {}", e))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read checkpoint: {}", e))?;
+
+        let mut lines = contents.lines();
+        if lines.next() != Some(HEADER) {
+            return Err("Not a checkpoint file.".to_string());
+        }
+
+        let entries = lines
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+                (key.to_string(), value.to_string())
+            })
+            .collect();
+
+        Ok(Self { entries })
+    }
+}
+
+pub fn encode_gaps(candidates: &[Vec<usize>]) -> String {
+    candidates
+        .iter()
+        .map(|gaps| {
+            gaps.iter()
+                .map(|gap| gap.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        })
+        .collect::<Vec<String>>()
+        .join(";")
+}
+
+pub fn decode_gaps(value: &str) -> Result<Vec<Vec<usize>>, String> {
+    value
+        .split(';')
+        .map(|gaps| {
+            gaps.split(',')
+                .map(|gap| gap.parse::<usize>())
+                .collect::<Result<Vec<usize>, _>>()
+                .map_err(|_| "Checkpoint has invalid gaps.".to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let path = env::temp_dir().join("shellsort_test_checkpoint_round_trip");
+
+        let mut checkpoint = Checkpoint::new("test");
+        checkpoint.set("length", 1000);
+        checkpoint.set("probability", 0.1);
+        checkpoint.set("gaps", encode_gaps(&[vec![1, 4, 13], vec![1]]));
+        let mut results = SorterResults::with_metrics(
+            "Quicksort".to_string(),
+            &["comparisons", "max depth"],
+            true,
+        );
+        results.add(&[12, 3]);
+        results.add(&[9, 4]);
+        checkpoint.set_results("results", &results);
+        checkpoint.set("length", 2000);
+        checkpoint.save(&path).unwrap();
+
+        let loaded = Checkpoint::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.get("mode").unwrap(), "test");
+        assert_eq!(loaded.parse::<usize>("length").unwrap(), 2000, "set replaces values");
+        assert_eq!(loaded.parse::<f64>("probability").unwrap(), 0.1);
+        assert_eq!(decode_gaps(loaded.get("gaps").unwrap()).unwrap(), vec![vec![1, 4, 13], vec![1]]);
+        assert!(loaded.expect("length", 2000).is_ok());
+        assert!(loaded.expect("length", 1000).is_err(), "different settings are rejected");
+        assert!(loaded.get("missing").is_err());
+
+        let mut loaded_results = SorterResults::with_metrics(
+            "Quicksort".to_string(),
+            &["comparisons", "max depth"],
+            true,
+        );
+        loaded.load_results("results", &mut loaded_results).unwrap();
+        assert_eq!(loaded_results.accumulators, results.accumulators, "same statistics");
+        assert_eq!(loaded.get("results.max_depth").unwrap(), "2 7 3.5 0.5 4 3 [3,4]");
+    }
+}
@@ -0,0 +1,329 @@
+use crate::{
+    checkpoint::{encode_gaps, Checkpoint, CheckpointFile},
+    sort::{Experiment, PairedResults, PairedRounds, SorterReport},
+};
+
+pub struct CompareParameters {
+    pub experiment: Experiment,
+    pub checkpoint_interval: usize,
+    //The results are the same for any number of threads, so a comparison
+    //can be resumed with another.
+    pub threads: usize,
+    //Medians, percentiles and anything drawn from every round need the
+    //values of the rounds, which are otherwise only summarized.
+    pub keep_values: bool,
+}
+
+fn save_checkpoint(
+    parameters: &CompareParameters,
+    candidates: &[Vec<usize>],
+    completed: usize,
+    results: &PairedResults,
+    file: &CheckpointFile,
+) -> Result<(), String> {
+    let mut checkpoint = Checkpoint::new("compare");
+    checkpoint.set("length", parameters.experiment.length);
+    checkpoint.set("seed", parameters.experiment.seed);
+    checkpoint.set("distribution", &parameters.experiment.distribution);
+    checkpoint.set("round_mode", parameters.experiment.mode);
+    checkpoint.set("candidates", encode_gaps(candidates));
+    checkpoint.set("keep_values", parameters.keep_values);
+    checkpoint.set("completed", completed);
+
+    for (i, (results, differences)) in results.results
+        .iter()
+        .zip(results.differences.iter())
+        .enumerate()
+    {
+        checkpoint.set_results(&format!("results.{}", i), results);
+        checkpoint.set(&format!("differences.{}.comparisons", i), &differences.comparisons);
+        checkpoint.set(&format!("differences.{}.moves", i), &differences.moves);
+    }
+
+    checkpoint.save(&file.path)
+}
+
+//The number of rounds is not part of the check, so a finished comparison
+//can be resumed with more rounds to extend it, though not with fewer.
+fn load_checkpoint(
+    parameters: &CompareParameters,
+    candidates: &[Vec<usize>],
+    file: &CheckpointFile,
+) -> Result<Option<(usize, PairedResults)>, String> {
+    let Some(checkpoint) = file.load_for_resume()? else {
+        return Ok(None);
+    };
+
+    checkpoint.expect("mode", "compare")?;
+    checkpoint.expect("length", parameters.experiment.length)?;
+    checkpoint.expect("seed", parameters.experiment.seed)?;
+    checkpoint.expect("distribution", &parameters.experiment.distribution)?;
+    checkpoint.expect("round_mode", parameters.experiment.mode)?;
+    checkpoint.expect("candidates", encode_gaps(candidates))?;
+    checkpoint.expect("keep_values", parameters.keep_values)?;
+
+    let completed: usize = checkpoint.parse("completed")?;
+    if completed > parameters.experiment.rounds {
+        return Err(format!(
+            "Checkpoint already has {} rounds, more than the {} asked for.",
+            completed, parameters.experiment.rounds,
+        ));
+    }
+
+    let mut results = PairedResults::new(candidates.len(), parameters.keep_values);
+    for (i, (results, differences)) in results.results
+        .iter_mut()
+        .zip(results.differences.iter_mut())
+        .enumerate()
+    {
+        checkpoint.load_results(&format!("results.{}", i), results)?;
+        differences.comparisons = checkpoint.parse(&format!("differences.{}.comparisons", i))?;
+        differences.moves = checkpoint.parse(&format!("differences.{}.moves", i))?;
+    }
+
+    if results.results.iter().any(|results| results.rounds() != completed as u64) {
+        return Err("Checkpoint results do not match its completed rounds.".to_string());
+    }
+
+    Ok(Some((completed, results)))
+}
+
+//Calls resumed with the rounds already done when a checkpoint is resumed.
+pub fn compare_sequences(
+    parameters: &CompareParameters,
+    candidates: &[Vec<usize>],
+    checkpoint: Option<&CheckpointFile>,
+    mut resumed: impl FnMut(usize),
+) -> Result<PairedResults, String> {
+    let mut rounds = PairedRounds::new(&parameters.experiment, parameters.threads);
+
+    let loaded = match checkpoint {
+        Some(file) => load_checkpoint(parameters, candidates, file)?,
+        None => None,
+    };
+
+    let mut results = match loaded {
+        Some((completed, results)) => {
+            resumed(completed);
+            rounds.skip(completed)?;
+            results
+        },
+        None => PairedResults::new(candidates.len(), parameters.keep_values),
+    };
+
+    let interval = match (checkpoint, parameters.checkpoint_interval) {
+        (Some(_), interval) if interval > 0 => interval,
+        _ => parameters.experiment.rounds,
+    };
+
+    while rounds.completed() < parameters.experiment.rounds {
+        let batch = interval.min(parameters.experiment.rounds - rounds.completed());
+        rounds.run(batch, candidates, |round| results.add(round))?;
+
+        if let Some(file) = checkpoint {
+            save_checkpoint(parameters, candidates, rounds.completed(), &results, file)?;
+        }
+    }
+
+    Ok(results)
+}
+
+pub struct RankingRow {
+    pub name: String,
+    pub gap_count: usize,
+    pub comparisons: f64,
+    pub comparisons_stddev: f64,
+    pub moves: f64,
+    pub moves_stddev: f64,
+}
+
+//Compared sequences ordered by average comparisons, then by average moves.
+pub struct Ranking {
+    pub rows: Vec<RankingRow>,
+}
+
+impl Ranking {
+    pub fn new(
+        length: usize,
+        candidates: &[(String, &[usize], &SorterReport)],
+    ) -> Result<Self, String> {
+        let mut rows = candidates
+            .iter()
+            .map(|&(ref name, gaps, report)| {
+                let comparisons = report.metric("comparisons")
+                    .ok_or("Report is missing comparisons.")?;
+                let moves = report.metric("moves")
+                    .ok_or("Report is missing moves.")?;
+
+                Ok(RankingRow {
+                    name: name.clone(),
+                    //Gaps as large as the array do nothing.
+                    gap_count: gaps.iter().filter(|&&gap| gap < length).count(),
+                    comparisons: comparisons.average,
+                    comparisons_stddev: comparisons.stddev,
+                    moves: moves.average,
+                    moves_stddev: moves.stddev,
+                })
+            })
+            .collect::<Result<Vec<RankingRow>, String>>()?;
+
+        rows.sort_by(|a, b| {
+            a.comparisons
+                .total_cmp(&b.comparisons)
+                .then(a.moves.total_cmp(&b.moves))
+        });
+
+        Ok(Self { rows })
+    }
+
+    pub fn print(&self) {
+        let width = self.rows
+            .iter()
+            .map(|row| row.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("Gap sequence".len());
+
+        println!("Ranking by average comparisons:");
+        println!(
+            "{:<4}  {:<width$}  {:>14}  {:>10}  {:>14}  {:>10}  {:>4}",
+            "Rank", "Gap sequence", "Comparisons", "Std Dev", "Moves", "Std Dev", "Gaps",
+        );
+
+        for (i, row) in self.rows.iter().enumerate() {
+            println!(
+                "{:<4}  {:<width$}  {:>14.2}  {:>10.2}  {:>14.2}  {:>10.2}  {:>4}",
+                i + 1,
+                row.name,
+                row.comparisons,
+                row.comparisons_stddev,
+                row.moves,
+                row.moves_stddev,
+                row.gap_count,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::{perform_paired_rounds, Distribution, RoundMode};
+    use std::{env, fs};
+
+    #[test]
+    fn test_compare_resumes_bit_identical() {
+        let path = env::temp_dir().join("shellsort_test_compare_resume");
+        let _ = fs::remove_file(&path);
+        let file = CheckpointFile {
+            path: path.clone(),
+            resume: true,
+        };
+
+        let candidates = vec![vec![1, 4, 13, 40], vec![1, 4, 10, 23, 57]];
+        let mut parameters = CompareParameters {
+            experiment: Experiment {
+                length: 300,
+                seed: 3,
+                rounds: 12,
+                distribution: Distribution::Uniform,
+                mode: RoundMode::Independent,
+            },
+            checkpoint_interval: 5,
+            threads: 0,
+            keep_values: true,
+        };
+
+        let uninterrupted = compare_sequences(&parameters, &candidates, None, |_| ()).unwrap();
+
+        //Stop after 7 rounds, then resume up to 12 on other threads.
+        parameters.experiment.rounds = 7;
+        compare_sequences(&parameters, &candidates, Some(&file), |_| ()).unwrap();
+        parameters.experiment.rounds = 12;
+        parameters.threads = 3;
+        let mut resumed_after = 0;
+        let resumed = compare_sequences(
+            &parameters, &candidates, Some(&file), |completed| resumed_after = completed,
+        ).unwrap();
+        assert_eq!(resumed_after, 7, "resumed after the first run");
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(resumed.rounds(), 12, "every round");
+        for (uninterrupted, resumed) in uninterrupted.results.iter().zip(resumed.results.iter()) {
+            assert_eq!(uninterrupted.accumulators, resumed.accumulators, "same rounds");
+        }
+        assert_eq!(uninterrupted.differences, resumed.differences, "same differences");
+    }
+
+    #[test]
+    fn test_compare_rejects_checkpoint_with_more_rounds() {
+        let path = env::temp_dir().join("shellsort_test_compare_more_rounds");
+        let _ = fs::remove_file(&path);
+        let file = CheckpointFile {
+            path: path.clone(),
+            resume: true,
+        };
+
+        let candidates = vec![vec![1, 4, 13], vec![1, 5, 19]];
+        let mut parameters = CompareParameters {
+            experiment: Experiment {
+                length: 100,
+                seed: 2,
+                rounds: 8,
+                distribution: Distribution::Uniform,
+                mode: RoundMode::Independent,
+            },
+            checkpoint_interval: 4,
+            threads: 0,
+            keep_values: false,
+        };
+
+        compare_sequences(&parameters, &candidates, Some(&file), |_| ()).unwrap();
+        parameters.experiment.rounds = 5;
+        let resumed = compare_sequences(&parameters, &candidates, Some(&file), |_| ());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            resumed.err(),
+            Some("Checkpoint already has 8 rounds, more than the 5 asked for.".to_string()),
+        );
+    }
+
+    #[test]
+    fn test_ranking() {
+        let candidates = vec![vec![1], vec![1, 4, 10, 23], vec![1, 4, 10, 23, 57, 132]];
+        let results = perform_paired_rounds(
+            &Experiment {
+                length: 100,
+                seed: 1,
+                rounds: 20,
+                distribution: Distribution::Uniform,
+                mode: RoundMode::Independent,
+            },
+            &candidates,
+            false,
+        ).unwrap();
+        let reports: Vec<SorterReport> = results.results
+            .iter()
+            .map(|results| SorterReport::try_from(results).unwrap())
+            .collect();
+
+        let names = ["insertion", "short", "long"];
+        let ranking = Ranking::new(100, &[
+            (names[0].to_string(), &candidates[0][..], &reports[0]),
+            (names[1].to_string(), &candidates[1][..], &reports[1]),
+            (names[2].to_string(), &candidates[2][..], &reports[2]),
+        ]).unwrap();
+
+        assert_eq!(ranking.rows.last().unwrap().name, "insertion", "insertion sort is worst");
+        assert!(
+            ranking.rows.windows(2).all(|rows| rows[0].comparisons <= rows[1].comparisons),
+            "ordered by comparisons",
+        );
+        assert_eq!(
+            ranking.rows.iter().find(|row| row.name == "long").unwrap().gap_count,
+            5,
+            "132 is too large for 100 elements",
+        );
+    }
+}
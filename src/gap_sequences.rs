@@ -1,4 +1,4 @@
-use std::{num::ParseIntError, str::FromStr};
+use std::{borrow::Cow, fmt, num::ParseIntError, str::FromStr};
 
 use self::{
     n_smooth::list_n_smooth,
@@ -8,6 +8,7 @@ use self::{
 mod n_smooth;
 mod math;
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum GapSequence {
     Shell1959,
     FrankLazarus1960,
@@ -26,6 +27,25 @@ pub enum GapSequence {
 }
 
 impl GapSequence {
+    //Every sequence that can be chosen by name, oldest first.
+    pub fn all() -> Vec<GapSequence> {
+        vec![
+            Self::Shell1959,
+            Self::FrankLazarus1960,
+            Self::Hibbard1963,
+            Self::PapernovStasevich1965,
+            Self::Pratt1971,
+            Self::Knuth1973,
+            Self::Sedgewick1982,
+            Self::IncerpiSedgewick1985,
+            Self::Sedgewick1986,
+            Self::GonnetBaezaYates1991,
+            Self::Tokuda1992,
+            Self::Ciura2001,
+            Self::Lee2021,
+        ]
+    }
+
     pub fn to_vec(&self, array_len: usize) -> Vec<usize> {
         match self {
             Self::Shell1959 => shell_1959(array_len),
@@ -46,6 +66,31 @@ impl GapSequence {
     }
 }
 
+//The name accepted by from_str, or the gaps separated by ',' if custom.
+impl fmt::Display for GapSequence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Shell1959 => write!(f, "shell_1959"),
+            Self::FrankLazarus1960 => write!(f, "frank_lazarus_1960"),
+            Self::Hibbard1963 => write!(f, "hibbard_1963"),
+            Self::PapernovStasevich1965 => write!(f, "papernov_stasevich_1965"),
+            Self::Pratt1971 => write!(f, "pratt_1971"),
+            Self::Knuth1973 => write!(f, "knuth_1973"),
+            Self::Sedgewick1982 => write!(f, "sedgewick_1982"),
+            Self::IncerpiSedgewick1985 => write!(f, "incerpi_sedgewick_1985"),
+            Self::Sedgewick1986 => write!(f, "sedgewick_1986"),
+            Self::GonnetBaezaYates1991 => write!(f, "gonnet_baezayates_1991"),
+            Self::Tokuda1992 => write!(f, "tokuda_1992"),
+            Self::Ciura2001 => write!(f, "ciura_2001"),
+            Self::Lee2021 => write!(f, "lee_2021"),
+            Self::Custom(gaps) => {
+                let gaps: Vec<String> = gaps.iter().map(|gap| gap.to_string()).collect();
+                write!(f, "{}", gaps.join(","))
+            },
+        }
+    }
+}
+
 impl FromStr for GapSequence {
     type Err = ParseIntError;
 
@@ -63,7 +108,8 @@ impl FromStr for GapSequence {
             "sedgewick_1986" => Ok(Self::Sedgewick1986),
             "gonnet_baezayates_1991" => Ok(Self::GonnetBaezaYates1991),
             "tokuda_1992" => Ok(Self::Tokuda1992),
-            "ciura_2021" => Ok(Self::Ciura2001),
+            //ciura_2021 was the only name of the sequence before, so it is kept.
+            "ciura_2001" | "ciura_2021" => Ok(Self::Ciura2001),
             "lee_2021" => Ok(Self::Lee2021),
             _ => {
                 let gaps: Result<Vec<usize>, ParseIntError> = value
@@ -81,6 +127,39 @@ impl FromStr for GapSequence {
 }
 
 
+//Anything that gives the gaps to sort an array of a given length with,
+//either fixed gaps or a sequence generated for that length.
+pub trait Gaps {
+    fn gaps(&self, array_len: usize) -> Cow<'_, [usize]>;
+}
+
+impl Gaps for [usize] {
+    fn gaps(&self, _array_len: usize) -> Cow<'_, [usize]> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl<const N: usize> Gaps for [usize; N] {
+    fn gaps(&self, _array_len: usize) -> Cow<'_, [usize]> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl Gaps for Vec<usize> {
+    fn gaps(&self, _array_len: usize) -> Cow<'_, [usize]> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl Gaps for GapSequence {
+    fn gaps(&self, array_len: usize) -> Cow<'_, [usize]> {
+        match self {
+            Self::Custom(gaps) => Cow::Borrowed(gaps),
+            _ => Cow::Owned(self.to_vec(array_len)),
+        }
+    }
+}
+
 pub fn shell_1959(array_len: usize) -> Vec<usize> {
     let mut gaps: Vec<usize> = Vec::new();
     
@@ -192,7 +271,7 @@ pub fn incerpi_sedgewick_1985(array_len: usize) -> Vec<usize> {
         let r = (k2 + k2.sqrt()).sqrt().floor() as usize;
             
         for q in a_seq.len()..r {
-            current_power = current_power * FIVE_HALVES;
+            current_power *= FIVE_HALVES;
             let mut n = current_power.ceil() as usize;
             loop {
                 let coprime = (0..q).all(|p| gcd(n, a_seq[p]) == 1);
@@ -298,4 +377,26 @@ pub fn lee_2021(array_len: usize) -> Vec<usize> {
 
         gaps.push(gap);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_round_trip() {
+        let mut sequences = GapSequence::all();
+        sequences.push(GapSequence::Custom(vec![1, 4, 10]));
+
+        for sequence in sequences {
+            assert_eq!(
+                GapSequence::from_str(&sequence.to_string()),
+                Ok(sequence.clone()),
+                "{}",
+                sequence,
+            );
+        }
+
+        assert_eq!(GapSequence::from_str("ciura_2021"), Ok(GapSequence::Ciura2001), "old name");
+    }
+}
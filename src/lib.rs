@@ -0,0 +1,24 @@
+//Instrumented Shellsort and quicksort, the gap sequences and inputs to
+//measure them with, and the reports summarizing those measurements. The
+//command line program in main.rs is built on top of this library.
+
+pub use self::{
+    gap_sequences::{GapSequence, Gaps},
+    sort::{
+        perform_rounds, perform_rounds_parallel, quicksort, shellsort, shellsort_by,
+        shellsort_by_key, Counted, Distribution, Experiment, MetricReport, QuicksortResult,
+        QuicksortSorter, ReportSettings, RoundMode, ShellsortResult, ShellsortSorter, Sorter,
+        SorterReport, SorterResults,
+    },
+};
+
+pub mod sort;
+pub mod gap_sequences;
+pub mod optimize;
+pub mod compare;
+pub mod checkpoint;
+pub mod output;
+pub mod sweep;
+pub mod chart;
+pub mod settings;
+pub mod run;
@@ -1,74 +1,47 @@
-use std::str::FromStr;
+use std::process;
 
 use argparse::{ArgumentParser, Print, Store, StoreTrue};
-use gap_sequences::GapSequence;
-use sort::{perform_rounds, ReportError, SortReport};
-
-mod sort;
-mod gap_sequences;
-
-enum MaxDistanceOption {
-    Length,
-    Custom(f64),
-}
-
-impl FromStr for MaxDistanceOption {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "" => Ok(MaxDistanceOption::Length),
-            _ => {
-                let max_distance = s.parse::<f64>()
-                .map_err(|_| "Invalid max distance provided.")?;
-
-                Ok(MaxDistanceOption::Custom(max_distance))
-            }
-        }
-    }
-}
-
-struct Options {
-    seed: u64,
-    rounds: usize,
-    length: usize,
-    gap_sequence: String,
-    optimize: bool,
-    optimize_moves: bool,
-    quicksort: bool,
-    max_distance: MaxDistanceOption,
-    probability: f64,
-}
+use shellsort::{run::run, settings::Settings};
 
 fn main() {
-    let mut options = Options { 
-        seed: 0,
-        rounds: 100,
-        length: 100,
-        gap_sequence: String::from(""),
-        optimize: false,
-        optimize_moves: false,
-        quicksort: false,
-        max_distance: MaxDistanceOption::Length,
-        probability: 1.0,
-    };
+    let mut settings = Settings::default();
     
     let seed_help = format!(
         "Seed for random number generator. Default is {}.",
-        options.seed,
+        settings.seed,
     );
     let rounds_help = format!(
         "Number of rounds to run. Default is {}.",
-        options.rounds
+        settings.rounds
     );
     let length_help = format!(
         "Length of the array to be sorted. Default is {}.",
-        options.length,
+        settings.length,
+    );
+    let candidates_help = format!(
+        "If used with '-o', the most candidate gaps tried at each step. \
+        Default is {}.",
+        settings.candidates,
+    );
+    let initial_rounds_help = format!(
+        "If used with '-o', rounds given to every candidate before the clearly \
+        worse ones are dropped. 0 gives every candidate all rounds. Default is {}.",
+        settings.initial_rounds,
     );
     let probability_help = format!(
         "Probability of each element moving in a swap during shuffling. \
         Default is {}.",
-        options.probability,
+        settings.probability,
+    );
+    let checkpoint_interval_help = format!(
+        "If used with '--compare' and '--checkpoint', rounds between \
+        checkpoints. Default is {}.",
+        settings.checkpoint_interval,
+    );
+    let confidence_help = format!(
+        "Confidence level of the interval reported for every average. \
+        Default is {}.",
+        settings.confidence,
     );
     
     {
@@ -84,28 +57,39 @@ fn main() {
             "Show version",
         );
 
-        arg_parser.refer(&mut options.seed)
+        arg_parser.refer(&mut settings.seed)
         .add_option(
             &["-s", "--seed"], 
             Store,
             &seed_help,
         );
 
-        arg_parser.refer(&mut options.rounds)
+        arg_parser.refer(&mut settings.rounds)
         .add_option(
             &["-r", "--rounds"], 
             Store,
             &rounds_help,
         );
 
-        arg_parser.refer(&mut options.length)
+        arg_parser.refer(&mut settings.length)
         .add_option(
             &["-l", "--length"], 
             Store,
             &length_help,
         );
 
-        arg_parser.refer(&mut options.gap_sequence)
+        arg_parser.refer(&mut settings.threads)
+        .add_option(
+            &["-t", "--threads"],
+            Store,
+            "Perform the rounds on this many threads, in single runs, \
+            '--compare', '--sweep' and the search of '-o'. Every round gets its \
+            own seed derived from the seed and its index, so results are the \
+            same for any number of threads. Cannot be used with '--floats', \
+            '--exhaustive' or cumulative rounds.",
+        );
+
+        arg_parser.refer(&mut settings.gap_sequence)
         .add_option(
             &["-g", "--gap-sequence"],
             Store,
@@ -113,28 +97,71 @@ fn main() {
                 Options are given by running this program with -g ls.",
         );
 
-        arg_parser.refer(&mut options.optimize)
+        arg_parser.refer(&mut settings.compare)
+        .add_option(
+            &["--compare"],
+            Store,
+            "Compare several gap sequences on identical arrays. Provide \
+                the sequences separated by ';', as accepted by -g, or 'all' \
+                for every named sequence. Differences are reported against \
+                the first one, and the sequences are ranked at the end.",
+        );
+
+        arg_parser.refer(&mut settings.optimize)
         .add_option(
             &["-o", "--optimize"],
             argparse::StoreTrue,
             "Adjust the gap sequence until it is optimal for the length.",
         );
 
-        arg_parser.refer(&mut options.optimize_moves)
+        arg_parser.refer(&mut settings.optimize_moves)
         .add_option(
             &["-m", "--optimize-moves"],
             argparse::StoreTrue,
             "If used with '-o', optimize for number of moves instead of comparisons.",
         );
 
-        arg_parser.refer(&mut options.quicksort)
+        arg_parser.refer(&mut settings.candidates)
+        .add_option(
+            &["-c", "--candidates"],
+            Store,
+            &candidates_help,
+        );
+
+        arg_parser.refer(&mut settings.initial_rounds)
+        .add_option(
+            &["--initial-rounds"],
+            Store,
+            &initial_rounds_help,
+        );
+
+        arg_parser.refer(&mut settings.quicksort)
         .add_option(
             &["-q", "--quicksort"],
             StoreTrue,
             "Also run quicksort for comparison.",
         );
 
-        arg_parser.refer(&mut options.max_distance)
+        arg_parser.refer(&mut settings.exhaustive)
+        .add_option(
+            &["--exhaustive"],
+            StoreTrue,
+            "Sort every permutation of the length instead of random arrays, \
+            reporting exact results. Works with '-g', '--compare' and '-q' \
+            for lengths up to 12.",
+        );
+
+        arg_parser.refer(&mut settings.input)
+        .add_option(
+            &["-i", "--input"],
+            Store,
+            "Specifies the arrays to be sorted, such as 'uniform', \
+            'bounded_swaps' to shuffle with '-d' and '-p', or 'k_sorted:10'. \
+            Options are given by running this program with -i ls. Default \
+            is bounded_swaps.",
+        );
+
+        arg_parser.refer(&mut settings.max_distance)
         .add_option(
             &["-d", "--max-distance"],
             Store,
@@ -142,64 +169,149 @@ fn main() {
             swap during shuffling. Default is the length of the array.",
         );
 
-        arg_parser.refer(&mut options.probability)
+        arg_parser.refer(&mut settings.probability)
         .add_option(
             &["-p", "--probability"],
             Store,
             &probability_help,
         );
+
+        arg_parser.refer(&mut settings.floats)
+        .add_option(
+            &["--floats"],
+            StoreTrue,
+            "Sort f64 values, by default the inputs of '-i' converted. Only \
+            for single runs, without '-t', '-o', '--compare', '--sweep' or \
+            '--exhaustive'.",
+        );
+
+        arg_parser.refer(&mut settings.float_values)
+        .add_option(
+            &["--float-values"],
+            Store,
+            "Either 'permutation' for the inputs of '-i', 'uniform' for \
+            random values in [0, 1), or 'specials' for random values in \
+            [-1, 1) with signed zeros and infinities. Implies '--floats'. \
+            Default is permutation.",
+        );
+
+        arg_parser.refer(&mut settings.nan_fraction)
+        .add_option(
+            &["--nan-fraction"],
+            Store,
+            "Probability of each f64 value being replaced by NaN. Implies \
+            '--floats'. Default is 0.",
+        );
+
+        arg_parser.refer(&mut settings.nan_placement)
+        .add_option(
+            &["--nan-placement"],
+            Store,
+            "Either 'first' or 'last', where NaNs are placed when sorting \
+            f64 values. Default is last.",
+        );
+
+        arg_parser.refer(&mut settings.round_mode)
+        .add_option(
+            &["--round-mode"],
+            Store,
+            "Either 'independent', where every round shuffles a sorted array, \
+            or 'cumulative', where every round shuffles the array of the \
+            previous round. Default is independent.",
+        );
+
+        arg_parser.refer(&mut settings.checkpoint)
+        .add_option(
+            &["--checkpoint"],
+            Store,
+            "File to periodically save the state of '-o' or '--compare' to.",
+        );
+
+        arg_parser.refer(&mut settings.resume)
+        .add_option(
+            &["--resume"],
+            StoreTrue,
+            "Resume from the file given to '--checkpoint' if it exists. \
+            The other options must match the interrupted run.",
+        );
+
+        arg_parser.refer(&mut settings.checkpoint_interval)
+        .add_option(
+            &["--checkpoint-interval"],
+            Store,
+            &checkpoint_interval_help,
+        );
         
+        arg_parser.refer(&mut settings.percentiles)
+        .add_option(
+            &["--percentiles"],
+            Store,
+            "Percentiles from 0 to 100 to report for every metric along with \
+            the median, separated by ','. For example '5,95'. Without them, \
+            rounds are summarized without keeping every result.",
+        );
+
+        arg_parser.refer(&mut settings.confidence)
+        .add_option(
+            &["--confidence"],
+            Store,
+            &confidence_help,
+        );
+
+        arg_parser.refer(&mut settings.format)
+        .add_option(
+            &["--format"],
+            Store,
+            "Either 'text', 'json', 'csv' or 'markdown', how the reports and \
+            the parameters of the run are written. Default is text.",
+        );
+
+        arg_parser.refer(&mut settings.rounds_output)
+        .add_option(
+            &["--rounds-output"],
+            Store,
+            "File to write the result of every round to, tagged with the \
+            round and the seed its input was generated from. Written as CSV \
+            if the name ends in '.csv' and as JSON Lines otherwise.",
+        );
+
+        arg_parser.refer(&mut settings.sweep)
+        .add_option(
+            &["--sweep"],
+            Store,
+            "Sort at every length of a range instead of at '-l', such as \
+            '100..1000000 x2' for doubling lengths or '100..1000 +100' for \
+            evenly spaced ones, and fit the growth of the costs. Uses the \
+            sequence of '-g', or the sequences of '--compare', along with \
+            quicksort if '-q' is given. Without '-d', bounded swaps reach as \
+            far as each length.",
+        );
+
+        arg_parser.refer(&mut settings.chart)
+        .add_option(
+            &["--chart"],
+            Store,
+            "SVG file to draw a chart to. With '--sweep' it shows the average \
+            comparisons against length on log-log axes, otherwise a histogram \
+            of the comparisons of every round, with a panel per sorter when \
+            there are more than 3.",
+        );
+
         arg_parser.parse_args_or_exit();
     }
 
-    if options.gap_sequence.trim().to_lowercase() == "ls" {
+    if settings.gap_sequence.trim().to_lowercase() == "ls" {
         println!(include_str!("gap_sequences.txt"));
         return;
     }
 
-    if !options.optimize {
-        let gap_sequence = GapSequence::from_str(&options.gap_sequence)
-        .expect("Invalid gap sequence provided.");
-
-        let gaps: Vec<usize> = gap_sequence.to_vec(options.length);
-
-        let max_distance = match options.max_distance {
-            MaxDistanceOption::Length => options.length as f64,
-            MaxDistanceOption::Custom(max_distance) => max_distance,
-        }; 
-
-        println!(
-            "Sorting results on array of length {} for {} round(s) \
-            and maximum swap distance of {} with {:.1}% probability of each swap.", 
-            options.length,
-            options.rounds,
-            max_distance,
-            options.probability * 100.0,
-        );
-        println!("Shellsort performed with gap sequence: {:?}", gaps);
-        println!();
-
-        let results = perform_rounds(
-            options.length,
-            options.seed,
-            options.rounds,
-            &gaps,
-            options.quicksort,
-            max_distance,
-            options.probability,
-        ).unwrap();
-
-        let report = SortReport::try_from(results);
-
-        match report {
-            Ok(report) => {
-                report.print();
-            },
-            Err(ReportError::EmptyResults) => {
-                eprintln!("No results to report.")
-            }
-        }
-    } else {
-        //TODO: Here we should look for an optimal gap sequence for the given length.
+    if settings.input.trim().to_lowercase() == "ls" {
+        println!(include_str!("distributions.txt"));
+        return;
+    }
+
+    if let Err(error) = run(&settings, |progress| eprintln!("{}", progress)) {
+        eprintln!("{}", error);
+        process::exit(1);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,311 @@
+use std::fmt;
+
+use crate::{
+    checkpoint::{Checkpoint, CheckpointFile},
+    sort::{Experiment, ShellsortResult, SorterReport},
+};
+
+use self::racing::Race;
+
+mod racing;
+
+//Largest ratio between a candidate and the previous gap that will be tried.
+//Ciura's sequence never exceeds a ratio of 4 between consecutive gaps.
+const MAX_RATIO: f64 = 4.0;
+
+pub enum Objective {
+    Comparisons,
+    Moves,
+}
+
+impl Objective {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Objective::Comparisons => "comparisons",
+            Objective::Moves => "moves",
+        }
+    }
+
+    pub fn score(&self, report: &SorterReport) -> f64 {
+        report.metric(self.name()).map_or(f64::INFINITY, |metric| metric.average)
+    }
+
+    pub fn value(&self, result: &ShellsortResult) -> u64 {
+        match self {
+            Objective::Comparisons => result.comparisons,
+            Objective::Moves => result.moves,
+        }
+    }
+}
+
+//What the search reports as it goes, for the caller to show as it likes.
+pub enum SearchProgress<'a> {
+    Resumed {
+        gaps: &'a [usize],
+    },
+    Step {
+        gaps: &'a [usize],
+        objective: &'a Objective,
+        average: f64,
+        candidates: usize,
+        rounds_used: usize,
+        rounds: usize,
+    },
+}
+
+impl fmt::Display for SearchProgress<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SearchProgress::Resumed { gaps } => {
+                write!(f, "Resuming search from gap sequence {:?}.", gaps)
+            },
+            SearchProgress::Step { gaps, objective, average, candidates, rounds_used, rounds } => {
+                write!(
+                    f,
+                    "Gap sequence {:?}: average {} {:.2} ({} candidate(s), {} of {} rounds)",
+                    gaps,
+                    objective.name(),
+                    average,
+                    candidates,
+                    rounds_used,
+                    rounds,
+                )
+            },
+        }
+    }
+}
+
+pub struct SearchParameters {
+    pub experiment: Experiment,
+    pub objective: Objective,
+    pub candidates: usize,
+    pub initial_rounds: usize,
+    //Threads every batch of rounds is split between. The search is the same
+    //for any number of threads, so checkpoints do not record it.
+    pub threads: usize,
+}
+
+pub fn candidate_gaps(last: usize, length: usize, count: usize) -> Vec<usize> {
+    let low = last + 1;
+    let high = ((last as f64 * MAX_RATIO).ceil() as usize).min(length.saturating_sub(1));
+
+    if count == 0 || high < low {
+        return Vec::new();
+    }
+
+    if high - low < count {
+        return (low..=high).collect();
+    }
+
+    //Too many candidates to try them all, so spread them geometrically.
+    let ratio = high as f64 / low as f64;
+    let mut candidates: Vec<usize> = (0..count)
+        .map(|i| {
+            let t = if count > 1 {
+                i as f64 / (count - 1) as f64
+            } else {
+                0.5
+            };
+
+            ((low as f64 * ratio.powf(t)).round() as usize).clamp(low, high)
+        })
+        .collect();
+
+    candidates.dedup();
+    candidates
+}
+
+fn save_checkpoint(
+    parameters: &SearchParameters,
+    race: &Race,
+    file: &CheckpointFile,
+) -> Result<(), String> {
+    let mut checkpoint = Checkpoint::new("optimize");
+    checkpoint.set("length", parameters.experiment.length);
+    checkpoint.set("seed", parameters.experiment.seed);
+    checkpoint.set("rounds", parameters.experiment.rounds);
+    checkpoint.set("distribution", &parameters.experiment.distribution);
+    checkpoint.set("round_mode", parameters.experiment.mode);
+    checkpoint.set("objective", parameters.objective.name());
+    checkpoint.set("candidate_count", parameters.candidates);
+    checkpoint.set("initial_rounds", parameters.initial_rounds);
+    race.save(&mut checkpoint);
+
+    checkpoint.save(&file.path)
+}
+
+fn load_checkpoint(
+    parameters: &SearchParameters,
+    file: &CheckpointFile,
+) -> Result<Option<Race>, String> {
+    let Some(checkpoint) = file.load_for_resume()? else {
+        return Ok(None);
+    };
+
+    checkpoint.expect("mode", "optimize")?;
+    checkpoint.expect("length", parameters.experiment.length)?;
+    checkpoint.expect("seed", parameters.experiment.seed)?;
+    checkpoint.expect("rounds", parameters.experiment.rounds)?;
+    checkpoint.expect("distribution", &parameters.experiment.distribution)?;
+    checkpoint.expect("round_mode", parameters.experiment.mode)?;
+    checkpoint.expect("objective", parameters.objective.name())?;
+    checkpoint.expect("candidate_count", parameters.candidates)?;
+    checkpoint.expect("initial_rounds", parameters.initial_rounds)?;
+
+    Race::load(&checkpoint).map(Some)
+}
+
+pub fn optimize(
+    parameters: &SearchParameters,
+    checkpoint: Option<&CheckpointFile>,
+    mut progress: impl FnMut(SearchProgress),
+) -> Result<Vec<usize>, String> {
+    let objective = &parameters.objective;
+
+    let mut resumed = match checkpoint {
+        Some(file) => load_checkpoint(parameters, file)?,
+        None => None,
+    };
+
+    let mut gaps: Vec<usize> = match resumed {
+        Some(ref race) => {
+            progress(SearchProgress::Resumed { gaps: &race.candidates()[0] });
+            race.candidates()[0].clone()
+        },
+        None => vec![1],
+    };
+
+    loop {
+        let mut race = match resumed.take() {
+            Some(race) => race,
+            None => {
+                let last = gaps[gaps.len() - 1];
+
+                //The current sequence races against every extension of it, so
+                //the search stops as soon as no larger gap is an improvement.
+                let mut candidates: Vec<Vec<usize>> = vec![gaps.clone()];
+                for candidate in candidate_gaps(last, parameters.experiment.length, parameters.candidates) {
+                    let mut extended = gaps.clone();
+                    extended.push(candidate);
+                    candidates.push(extended);
+                }
+
+                Race::new(parameters, candidates)?
+            },
+        };
+
+        while !race.is_finished(parameters) {
+            race.run_batch(parameters)?;
+
+            if let Some(file) = checkpoint {
+                save_checkpoint(parameters, &race, file)?;
+            }
+        }
+
+        let outcome = race.outcome(parameters)?;
+        let candidates = race.candidates();
+
+        progress(SearchProgress::Step {
+            gaps: &candidates[outcome.winner],
+            objective,
+            average: objective.score(&outcome.report),
+            candidates: candidates.len(),
+            rounds_used: outcome.rounds_used,
+            rounds: candidates.len() * parameters.experiment.rounds,
+        });
+
+        if outcome.winner == 0 {
+            break;
+        }
+
+        gaps = candidates[outcome.winner].clone();
+    }
+
+    Ok(gaps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::{Distribution, RoundMode};
+
+    #[test]
+    fn test_candidate_gaps() {
+        assert_eq!(candidate_gaps(1, 100, 10), vec![2, 3, 4], "all candidates fit");
+        assert_eq!(candidate_gaps(10, 25, 100), (11..=24).collect::<Vec<_>>(), "capped by length");
+        assert_eq!(candidate_gaps(10, 11, 10), vec![], "no room for another gap");
+
+        let candidates = candidate_gaps(1000, 1_000_000, 8);
+        assert_eq!(candidates.len(), 8, "spread over the range");
+        assert_eq!(candidates[0], 1001, "starts just above the last gap");
+        assert_eq!(candidates[7], 4000, "ends at the maximum ratio");
+        assert!(candidates.windows(2).all(|w| w[0] < w[1]), "strictly increasing");
+    }
+
+    #[test]
+    fn test_optimize() {
+        let mut parameters = SearchParameters {
+            experiment: Experiment {
+                length: 50,
+                seed: 0,
+                rounds: 10,
+                distribution: Distribution::Uniform,
+                mode: RoundMode::Independent,
+            },
+            objective: Objective::Comparisons,
+            candidates: 8,
+            initial_rounds: 0,
+            threads: 0,
+        };
+
+        let mut steps = 0;
+        let gaps = optimize(&parameters, None, |_| steps += 1).unwrap();
+
+        assert_eq!(gaps[0], 1, "sequence starts at 1");
+        assert!(gaps.len() > 1, "a larger gap helps on 50 elements");
+        assert!(gaps.windows(2).all(|w| w[0] < w[1]), "increasing sequence");
+        assert!(gaps.iter().all(|gap| *gap < parameters.experiment.length), "gaps fit the array");
+        assert_eq!(steps, gaps.len(), "a step per gap and one that found nothing better");
+
+        parameters.threads = 3;
+        assert_eq!(optimize(&parameters, None, |_| ()).unwrap(), gaps, "same on threads");
+    }
+
+    #[test]
+    fn test_optimize_resumes_from_checkpoint() {
+        let path = std::env::temp_dir().join("shellsort_test_optimize_resume");
+        let _ = std::fs::remove_file(&path);
+        let file = CheckpointFile {
+            path: path.clone(),
+            resume: true,
+        };
+
+        let parameters = SearchParameters {
+            experiment: Experiment {
+                length: 60,
+                seed: 1,
+                rounds: 20,
+                distribution: Distribution::Uniform,
+                mode: RoundMode::Independent,
+            },
+            objective: Objective::Moves,
+            candidates: 6,
+            initial_rounds: 4,
+            threads: 0,
+        };
+
+        let uninterrupted = optimize(&parameters, None, |_| ()).unwrap();
+        let checkpointed = optimize(&parameters, Some(&file), |_| ()).unwrap();
+        let mut resumed_from = None;
+        let resumed = optimize(&parameters, Some(&file), |progress| {
+            if let SearchProgress::Resumed { gaps } = progress {
+                resumed_from = Some(gaps.to_vec());
+            }
+        }).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(checkpointed, uninterrupted, "checkpointing does not change the search");
+        assert_eq!(resumed, uninterrupted, "resuming reproduces the search");
+        assert!(resumed_from.is_some(), "the resumed search says so");
+    }
+}
@@ -0,0 +1,332 @@
+use crate::{
+    checkpoint::{decode_gaps, encode_gaps, Checkpoint},
+    sort::{
+        DifferenceAccumulator, Experiment, PairedRounds, SorterReport, SorterResults,
+        SHELLSORT_METRICS,
+    },
+};
+
+use super::SearchParameters;
+
+//How many standard errors a candidate's paired difference from the leader
+//may sit above zero before the candidate is dropped from the race.
+const Z: f64 = 1.96;
+
+pub struct RaceOutcome {
+    pub winner: usize,
+    pub report: SorterReport,
+    pub rounds_used: usize,
+}
+
+fn new_results(candidates: usize) -> Vec<SorterResults> {
+    (0..candidates)
+        .map(|_| SorterResults::with_metrics("Shellsort".to_string(), SHELLSORT_METRICS, false))
+        .collect()
+}
+
+fn new_differences(candidates: usize) -> Vec<Vec<DifferenceAccumulator>> {
+    vec![vec![DifferenceAccumulator::new(false); candidates]; candidates]
+}
+
+//Evaluates every candidate with a few rounds, then keeps doubling the rounds
+//given to the candidates that are not yet clearly worse than the leader.
+//Each batch uses its own seed, and every candidate in a batch sorts the
+//same arrays, so candidates are compared by their paired differences.
+//Only the statistics of the rounds are kept, with the differences in the
+//objective of every pair of candidates.
+pub struct Race {
+    candidates: Vec<Vec<usize>>,
+    results: Vec<SorterResults>,
+    //The objective of candidate j minus that of candidate i, for i < j.
+    differences: Vec<Vec<DifferenceAccumulator>>,
+    alive: Vec<usize>,
+    rounds_used: usize,
+    completed: usize,
+    batch: u64,
+    batch_rounds: usize,
+}
+
+impl Race {
+    pub fn new(
+        parameters: &SearchParameters,
+        candidates: Vec<Vec<usize>>,
+    ) -> Result<Self, String> {
+        if parameters.experiment.rounds == 0 || candidates.is_empty() {
+            return Err("Nothing to race.".to_string());
+        }
+
+        Ok(Self {
+            results: new_results(candidates.len()),
+            differences: new_differences(candidates.len()),
+            alive: (0..candidates.len()).collect(),
+            candidates,
+            rounds_used: 0,
+            completed: 0,
+            batch: 0,
+            batch_rounds: match parameters.initial_rounds {
+                0 => parameters.experiment.rounds,
+                initial_rounds => initial_rounds.min(parameters.experiment.rounds),
+            },
+        })
+    }
+
+    pub fn candidates(&self) -> &[Vec<usize>] {
+        &self.candidates
+    }
+
+    pub fn is_finished(&self, parameters: &SearchParameters) -> bool {
+        self.completed >= parameters.experiment.rounds
+            || (self.completed > 0 && self.alive.len() == 1)
+    }
+
+    //The average difference of candidate from leader, and its standard error.
+    fn difference(&self, leader: usize, candidate: usize) -> (f64, f64) {
+        if leader < candidate {
+            let difference = &self.differences[leader][candidate];
+            (difference.average(), difference.standard_error())
+        } else if candidate < leader {
+            let difference = &self.differences[candidate][leader];
+            (-difference.average(), difference.standard_error())
+        } else {
+            (0.0, 0.0)
+        }
+    }
+
+    pub fn run_batch(&mut self, parameters: &SearchParameters) -> Result<(), String> {
+        let objective = &parameters.objective;
+
+        let alive_gaps: Vec<&Vec<usize>> = self.alive
+            .iter()
+            .map(|&i| &self.candidates[i])
+            .collect();
+
+        let batch = Experiment {
+            seed: parameters.experiment.seed.wrapping_add(self.batch),
+            rounds: self.batch_rounds,
+            ..parameters.experiment.clone()
+        };
+
+        //The candidates that are alive stay in order, so i < j below.
+        let alive = &self.alive;
+        let results = &mut self.results;
+        let differences = &mut self.differences;
+        PairedRounds::new(&batch, parameters.threads).run(self.batch_rounds, &alive_gaps, |round| {
+            for (k, (&i, result)) in alive.iter().zip(round).enumerate() {
+                results[i].add(&[result.comparisons, result.moves]);
+
+                for (&j, other) in alive.iter().zip(round).skip(k + 1) {
+                    differences[i][j].add(objective.value(result), objective.value(other));
+                }
+            }
+        })?;
+
+        self.rounds_used += self.batch_rounds * self.alive.len();
+        self.completed += self.batch_rounds;
+        self.batch += 1;
+
+        if self.is_finished(parameters) {
+            return Ok(());
+        }
+
+        let metric = self.results[0].metrics
+            .iter()
+            .position(|&metric| metric == objective.name())
+            .ok_or("Results are missing the objective.".to_string())?;
+        let leader = self.alive
+            .iter()
+            .copied()
+            .min_by_key(|&i| self.results[i].accumulators[metric].sum())
+            .unwrap();
+
+        let mut survivors: Vec<usize> = Vec::with_capacity(self.alive.len());
+        for &i in self.alive.iter() {
+            let (average, standard_error) = self.difference(leader, i);
+
+            if average - Z * standard_error <= 0.0 {
+                survivors.push(i);
+            }
+        }
+
+        self.alive = survivors;
+        self.batch_rounds = self.completed.min(parameters.experiment.rounds - self.completed);
+
+        Ok(())
+    }
+
+    pub fn outcome(&self, parameters: &SearchParameters) -> Result<RaceOutcome, String> {
+        let objective = &parameters.objective;
+
+        let mut outcome: Option<RaceOutcome> = None;
+        for &i in self.alive.iter() {
+            let report = SorterReport::try_from(&self.results[i]).map_err(|e| e.to_string())?;
+            let better = match outcome {
+                Some(ref best) => objective.score(&report) < objective.score(&best.report),
+                None => true,
+            };
+
+            if better {
+                outcome = Some(RaceOutcome {
+                    winner: i,
+                    report,
+                    rounds_used: self.rounds_used,
+                });
+            }
+        }
+
+        Ok(outcome.unwrap())
+    }
+
+    pub fn save(&self, checkpoint: &mut Checkpoint) {
+        checkpoint.set("candidates", encode_gaps(&self.candidates));
+        checkpoint.set(
+            "alive",
+            self.alive
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<String>>()
+                .join(","),
+        );
+        checkpoint.set("rounds_used", self.rounds_used);
+        checkpoint.set("completed", self.completed);
+        checkpoint.set("batch", self.batch);
+        checkpoint.set("batch_rounds", self.batch_rounds);
+
+        for (i, results) in self.results.iter().enumerate() {
+            checkpoint.set_results(&format!("results.{}", i), results);
+
+            for (j, difference) in self.differences[i].iter().enumerate().skip(i + 1) {
+                checkpoint.set(&format!("differences.{}.{}", i, j), difference);
+            }
+        }
+    }
+
+    pub fn load(checkpoint: &Checkpoint) -> Result<Self, String> {
+        let candidates = decode_gaps(checkpoint.get("candidates")?)?;
+
+        let alive = checkpoint.get("alive")?
+            .split(',')
+            .map(|i| i.parse::<usize>())
+            .collect::<Result<Vec<usize>, _>>()
+            .ok()
+            .filter(|alive| alive.iter().all(|&i| i < candidates.len()))
+            .ok_or("Checkpoint has an invalid 'alive'.".to_string())?;
+
+        let mut results = new_results(candidates.len());
+        let mut differences = new_differences(candidates.len());
+        for (i, (results, differences)) in results
+            .iter_mut()
+            .zip(differences.iter_mut())
+            .enumerate()
+        {
+            checkpoint.load_results(&format!("results.{}", i), results)?;
+
+            for (j, difference) in differences.iter_mut().enumerate().skip(i + 1) {
+                *difference = checkpoint.parse(&format!("differences.{}.{}", i, j))?;
+            }
+        }
+
+        Ok(Self {
+            candidates,
+            results,
+            differences,
+            alive,
+            rounds_used: checkpoint.parse("rounds_used")?,
+            completed: checkpoint.parse("completed")?,
+            batch: checkpoint.parse("batch")?,
+            batch_rounds: checkpoint.parse("batch_rounds")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::{Distribution, RoundMode};
+    use crate::optimize::Objective;
+
+    fn parameters(initial_rounds: usize) -> SearchParameters {
+        SearchParameters {
+            experiment: Experiment {
+                length: 200,
+                seed: 0,
+                rounds: 80,
+                distribution: Distribution::Uniform,
+                mode: RoundMode::Independent,
+            },
+            objective: Objective::Comparisons,
+            candidates: 8,
+            initial_rounds,
+            threads: 0,
+        }
+    }
+
+    fn race(
+        parameters: &SearchParameters,
+        candidates: Vec<Vec<usize>>,
+    ) -> RaceOutcome {
+        let mut race = Race::new(parameters, candidates).unwrap();
+        while !race.is_finished(parameters) {
+            race.run_batch(parameters).unwrap();
+        }
+
+        race.outcome(parameters).unwrap()
+    }
+
+    #[test]
+    fn test_race_drops_bad_candidates() {
+        let candidates = vec![
+            vec![1],
+            vec![1, 2],
+            vec![1, 4, 13, 40],
+            vec![1, 4, 10, 23, 57, 132],
+        ];
+
+        let outcome = race(&parameters(5), candidates);
+
+        assert!(outcome.winner >= 2, "a full sequence beats insertion sort");
+        assert!(outcome.rounds_used < 80 * 4, "racing saves rounds");
+    }
+
+    #[test]
+    fn test_race_without_racing() {
+        let candidates = vec![vec![1], vec![1, 4, 13, 40]];
+
+        let outcome = race(&parameters(0), candidates);
+
+        assert_eq!(outcome.winner, 1, "knuth beats insertion sort");
+        assert_eq!(outcome.rounds_used, 160, "every candidate gets every round");
+    }
+
+    #[test]
+    fn test_race_resumes_from_checkpoint() {
+        let parameters = parameters(5);
+        let candidates = vec![
+            vec![1, 4, 13],
+            vec![1, 4, 13, 40],
+            vec![1, 4, 13, 45],
+            vec![1, 4, 13, 50],
+        ];
+
+        let uninterrupted = race(&parameters, candidates.clone());
+
+        //Save and reload the race after every batch.
+        let mut race = Race::new(&parameters, candidates).unwrap();
+        while !race.is_finished(&parameters) {
+            race.run_batch(&parameters).unwrap();
+
+            let mut checkpoint = Checkpoint::new("optimize");
+            race.save(&mut checkpoint);
+            race = Race::load(&checkpoint).unwrap();
+        }
+
+        let resumed = race.outcome(&parameters).unwrap();
+
+        assert_eq!(resumed.winner, uninterrupted.winner, "same winner");
+        assert_eq!(resumed.rounds_used, uninterrupted.rounds_used, "same rounds");
+        assert_eq!(
+            resumed.report.metrics[0].average,
+            uninterrupted.report.metrics[0].average,
+            "same average",
+        );
+    }
+}
@@ -0,0 +1,762 @@
+use std::{
+    fmt,
+    fmt::Write,
+    fs::File,
+    io::{self, BufWriter, Write as _},
+    path::Path,
+    str::FromStr,
+};
+
+use crate::sort::{
+    format_p_value, round_seed, Difference, Experiment, FloatKeys, MetricReport, PairedReport,
+    SorterReport, SorterResults,
+};
+
+//Metrics that get a column when every round is written as CSV.
+const ROUND_METRICS: [&str; 4] = ["comparisons", "moves", "swaps", "max depth"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Format::Text => write!(f, "text"),
+            Format::Json => write!(f, "json"),
+            Format::Csv => write!(f, "csv"),
+            Format::Markdown => write!(f, "markdown"),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "markdown" | "md" => Ok(Format::Markdown),
+            _ => Err(format!("Invalid format '{}'.", s)),
+        }
+    }
+}
+
+//The report of one sorter, with the gaps used if it is a Shellsort and its
+//differences from the first sorter if the run was a comparison.
+pub struct Entry {
+    pub gaps: Option<Vec<usize>>,
+    pub report: SorterReport,
+    pub paired: Option<PairedReport>,
+}
+
+//Everything needed to reproduce and read a run, rendered as machine-readable
+//text. Human-readable output is still printed by the reports themselves.
+pub struct Run<'a> {
+    pub experiment: &'a Experiment,
+    pub confidence: f64,
+    pub floats: Option<&'a FloatKeys>,
+    pub entries: Vec<Entry>,
+}
+
+impl Run<'_> {
+    pub fn render(&self, format: Format) -> Result<String, String> {
+        match format {
+            Format::Text => Err("Text output is printed by the reports.".to_string()),
+            Format::Json => Ok(self.to_json()),
+            Format::Csv => Ok(self.to_csv()),
+            Format::Markdown => Ok(self.to_markdown()),
+        }
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, Value)> {
+        let mut parameters = vec![
+            ("length", Value::Integer(self.experiment.length as u64)),
+            ("rounds", Value::Integer(self.experiment.rounds as u64)),
+            ("seed", Value::Integer(self.experiment.seed)),
+            ("distribution", Value::Text(self.experiment.distribution.to_string())),
+            ("round_mode", Value::Text(self.experiment.mode.to_string())),
+            ("confidence", Value::Float(self.confidence)),
+        ];
+
+        if let Some(keys) = self.floats {
+            parameters.push(("float_values", Value::Text(keys.values.to_string())));
+            parameters.push(("nan_fraction", Value::Float(keys.nan_fraction)));
+            parameters.push(("nan_placement", Value::Text(keys.placement.to_string())));
+        }
+
+        parameters
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\"parameters\":{");
+
+        for (i, (key, value)) in self.parameters().iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write!(json, "{}:{}", json_string(key), value.to_json()).unwrap();
+        }
+
+        json.push_str("},\"reports\":[");
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+
+            write!(json, "{{\"sorter\":{},\"gaps\":", json_string(&entry.report.name)).unwrap();
+            match entry.gaps {
+                Some(ref gaps) => write!(json, "{:?}", gaps).unwrap(),
+                None => json.push_str("null"),
+            }
+
+            json.push_str(",\"metrics\":[");
+            for (j, metric) in entry.report.metrics.iter().enumerate() {
+                if j > 0 {
+                    json.push(',');
+                }
+                json.push_str(&metric_json(metric));
+            }
+
+            json.push_str("],\"paired\":");
+            match entry.paired {
+                Some(ref paired) => {
+                    json.push('{');
+                    for (j, (name, difference)) in paired.differences().iter().enumerate() {
+                        if j > 0 {
+                            json.push(',');
+                        }
+                        write!(
+                            json,
+                            "{}:{}",
+                            json_string(name),
+                            difference_json(difference, paired.confidence),
+                        ).unwrap();
+                    }
+                    json.push('}');
+                },
+                None => json.push_str("null"),
+            }
+
+            json.push('}');
+        }
+
+        json.push_str("]}\n");
+        json
+    }
+
+    //One row per metric of every sorter, with the run parameters repeated on
+    //each row so files from several runs can simply be concatenated.
+    pub fn to_csv(&self) -> String {
+        let parameters = self.parameters();
+        let percentiles = self.percentile_names();
+
+        let mut header: Vec<String> = parameters.iter().map(|(key, _)| key.to_string()).collect();
+        header.extend([
+            "sorter", "gaps", "metric", "count", "average", "stddev", "sample_stddev",
+            "standard_error", "ci_low", "ci_high", "median",
+        ].iter().map(|s| s.to_string()));
+        header.extend(percentiles.iter().cloned());
+        header.extend([
+            "most", "fewest", "per_log2_factorial", "per_n_log2_n", "per_n",
+            "difference_average", "difference_stddev",
+            "difference_ci_low", "difference_ci_high", "wins", "ties", "win_rate",
+            "t_test_p_value", "wilcoxon_p_value",
+        ].iter().map(|s| s.to_string()));
+
+        let mut csv = csv_row(&header);
+
+        for entry in self.entries.iter() {
+            for metric in entry.report.metrics.iter() {
+                let mut row: Vec<String> = parameters
+                    .iter()
+                    .map(|(_, value)| value.to_string())
+                    .collect();
+
+                let (ci_low, ci_high) = optional_pair(metric.interval);
+                row.extend([
+                    entry.report.name.clone(),
+                    entry.gaps.as_ref().map_or(String::new(), |gaps| gaps_text(gaps)),
+                    metric.name.to_string(),
+                    metric.count.to_string(),
+                    metric.average.to_string(),
+                    metric.stddev.to_string(),
+                    metric.sample_stddev.to_string(),
+                    metric.standard_error.to_string(),
+                    ci_low,
+                    ci_high,
+                    optional(metric.median),
+                ]);
+
+                for &(_, value) in metric.percentiles.iter() {
+                    row.push(value.to_string());
+                }
+
+                row.push(metric.most.to_string());
+                row.push(metric.fewest.to_string());
+                row.push(optional(metric.normalized.per_log2_factorial));
+                row.push(optional(metric.normalized.per_n_log2_n));
+                row.push(optional(metric.normalized.per_n));
+
+                let difference = entry.paired
+                    .as_ref()
+                    .and_then(|paired| paired.difference(metric.name).map(|d| (paired, d)));
+
+                match difference {
+                    Some((paired, difference)) => {
+                        let (low, high) = optional_pair(difference.interval(paired.confidence));
+                        row.extend([
+                            difference.average.to_string(),
+                            difference.stddev.to_string(),
+                            low,
+                            high,
+                            difference.wins.to_string(),
+                            difference.ties.to_string(),
+                            difference.win_rate().to_string(),
+                            difference.t_test_p_value().to_string(),
+                            difference.wilcoxon_p_value.to_string(),
+                        ]);
+                    },
+                    None => row.extend(vec![String::new(); 9]),
+                }
+
+                csv.push_str(&csv_row(&row));
+            }
+        }
+
+        csv
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("## Parameters\n\n| Parameter | Value |\n| --- | --- |\n");
+
+        for (key, value) in self.parameters() {
+            writeln!(markdown, "| {} | {} |", key, value).unwrap();
+        }
+
+        let percentiles = self.percentile_names();
+        let confidence = (self.confidence * 1000.0).round() / 10.0;
+
+        for entry in self.entries.iter() {
+            write!(markdown, "\n## {}", entry.report.name).unwrap();
+            if let Some(ref gaps) = entry.gaps {
+                write!(markdown, " {:?}", gaps).unwrap();
+            }
+            markdown.push_str("\n\n");
+
+            let mut header = vec![
+                "Metric".to_string(),
+                "Average".to_string(),
+                "Std Dev".to_string(),
+                "Sample Std Dev".to_string(),
+                "Std Error".to_string(),
+                format!("{}% CI", confidence),
+                "Median".to_string(),
+            ];
+            header.extend(percentiles.iter().map(|p| p.to_uppercase()));
+            header.extend([
+                "Most", "Fewest", "Per log2(n!)", "Per n log2 n", "Per n",
+            ].iter().map(|s| s.to_string()));
+            markdown.push_str(&markdown_header(&header));
+
+            for metric in entry.report.metrics.iter() {
+                let mut row = vec![
+                    metric.name.to_string(),
+                    format!("{:.2}", metric.average),
+                    format!("{:.2}", metric.stddev),
+                    format!("{:.2}", metric.sample_stddev),
+                    format!("{:.2}", metric.standard_error),
+                    metric.interval.map_or(String::new(), |(low, high)| {
+                        format!("{:.2} to {:.2}", low, high)
+                    }),
+                    metric.median.map_or(String::new(), |median| format!("{:.2}", median)),
+                ];
+                row.extend(metric.percentiles.iter().map(|(_, value)| format!("{:.2}", value)));
+                row.push(metric.most.to_string());
+                row.push(metric.fewest.to_string());
+                for value in [
+                    metric.normalized.per_log2_factorial,
+                    metric.normalized.per_n_log2_n,
+                    metric.normalized.per_n,
+                ] {
+                    row.push(value.map_or(String::new(), |value| format!("{:.4}", value)));
+                }
+                markdown.push_str(&markdown_row(&row));
+            }
+
+            if let Some(ref paired) = entry.paired {
+                markdown.push('\n');
+                markdown.push_str(&markdown_header(&[
+                    "Difference in".to_string(),
+                    "Average".to_string(),
+                    "Std Dev".to_string(),
+                    format!("{}% CI", confidence),
+                    "Wins".to_string(),
+                    "Ties".to_string(),
+                    "Win Rate".to_string(),
+                    "t-test p".to_string(),
+                    "Wilcoxon p".to_string(),
+                ]));
+
+                for (name, difference) in paired.differences() {
+                    markdown.push_str(&markdown_row(&[
+                        name.to_string(),
+                        format!("{:.2}", difference.average),
+                        format!("{:.2}", difference.stddev),
+                        difference.interval(paired.confidence).map_or(String::new(), |(low, high)| {
+                            format!("{:.2} to {:.2}", low, high)
+                        }),
+                        format!("{} of {}", difference.wins, difference.count),
+                        difference.ties.to_string(),
+                        format!("{:.1}%", difference.win_rate() * 100.0),
+                        format_p_value(difference.t_test_p_value()),
+                        format_p_value(difference.wilcoxon_p_value),
+                    ]));
+                }
+            }
+        }
+
+        markdown
+    }
+
+    //Every report is made with the same settings, so the first metric has
+    //the percentiles of all of them.
+    fn percentile_names(&self) -> Vec<String> {
+        self.entries
+            .first()
+            .and_then(|entry| entry.report.metrics.first())
+            .map_or(Vec::new(), |metric| {
+                metric.percentiles.iter().map(|(p, _)| format!("p{}", p)).collect()
+            })
+    }
+}
+
+//Writes the result of every round of every sorter, as CSV if the path ends in
+//.csv and as JSON Lines otherwise. The results must have kept their values.
+//Each round is tagged with the seed its input was generated from, which is
+//derived from the seed of the run with round_seed.
+pub fn write_rounds(
+    path: &Path,
+    seed: u64,
+    sorters: &[(Option<&[usize]>, &SorterResults)],
+) -> Result<(), String> {
+    let csv = path.extension().is_some_and(|extension| extension == "csv");
+
+    let file = File::create(path)
+        .map_err(|e| format!("Could not create rounds file: {}", e))?;
+    let mut writer = BufWriter::new(file);
+
+    let written = if csv {
+        write_rounds_csv(&mut writer, seed, sorters)
+    } else {
+        write_rounds_json_lines(&mut writer, seed, sorters)
+    };
+
+    written
+        .and_then(|_| writer.flush())
+        .map_err(|e| format!("Could not write rounds file: {}", e))
+}
+
+fn round_values(results: &SorterResults) -> io::Result<Vec<&[u64]>> {
+    results.accumulators
+        .iter()
+        .map(|accumulator| accumulator.values())
+        .collect::<Option<Vec<&[u64]>>>()
+        .ok_or(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the values of every round were not kept",
+        ))
+}
+
+fn write_rounds_csv(
+    writer: &mut impl io::Write,
+    seed: u64,
+    sorters: &[(Option<&[usize]>, &SorterResults)],
+) -> io::Result<()> {
+    let mut header: Vec<String> = ["round", "seed", "sorter", "gaps"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    header.extend(ROUND_METRICS.iter().map(|metric| metric.replace(' ', "_")));
+    writer.write_all(csv_row(&header).as_bytes())?;
+
+    for &(gaps, results) in sorters.iter() {
+        let values = round_values(results)?;
+        let columns: Vec<Option<&[u64]>> = ROUND_METRICS
+            .iter()
+            .map(|metric| {
+                results.metrics
+                    .iter()
+                    .position(|name| name == metric)
+                    .map(|i| values[i])
+            })
+            .collect();
+
+        for round in 0..results.rounds() as usize {
+            let mut row = vec![
+                round.to_string(),
+                round_seed(seed, round).to_string(),
+                results.name.clone(),
+                gaps.map_or(String::new(), gaps_text),
+            ];
+
+            for column in columns.iter() {
+                row.push(column.map_or(String::new(), |values| values[round].to_string()));
+            }
+
+            writer.write_all(csv_row(&row).as_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_rounds_json_lines(
+    writer: &mut impl io::Write,
+    seed: u64,
+    sorters: &[(Option<&[usize]>, &SorterResults)],
+) -> io::Result<()> {
+    for &(gaps, results) in sorters.iter() {
+        let values = round_values(results)?;
+
+        for round in 0..results.rounds() as usize {
+            let mut line = format!(
+                "{{\"round\":{},\"seed\":{},\"sorter\":{},\"gaps\":",
+                round,
+                round_seed(seed, round),
+                json_string(&results.name),
+            );
+            match gaps {
+                Some(gaps) => write!(line, "{:?}", gaps).unwrap(),
+                None => line.push_str("null"),
+            }
+
+            for (metric, values) in results.metrics.iter().zip(values.iter()) {
+                write!(line, ",{}:{}", json_string(&metric.replace(' ', "_")), values[round])
+                    .unwrap();
+            }
+
+            line.push_str("}\n");
+            writer.write_all(line.as_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+enum Value {
+    Integer(u64),
+    Float(f64),
+    Text(String),
+}
+
+impl Value {
+    fn to_json(&self) -> String {
+        match self {
+            Value::Integer(value) => value.to_string(),
+            Value::Float(value) => json_number(*value),
+            Value::Text(value) => json_string(value),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Integer(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+fn metric_json(metric: &MetricReport) -> String {
+    let mut json = format!(
+        "{{\"name\":{},\"count\":{},\"average\":{},\"stddev\":{},\"sample_stddev\":{},\
+        \"standard_error\":{},\"confidence_interval\":{},\"median\":{},\"percentiles\":[",
+        json_string(metric.name),
+        metric.count,
+        json_number(metric.average),
+        json_number(metric.stddev),
+        json_number(metric.sample_stddev),
+        json_number(metric.standard_error),
+        json_interval(metric.interval),
+        metric.median.map_or("null".to_string(), json_number),
+    );
+
+    for (i, (p, value)) in metric.percentiles.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write!(
+            json,
+            "{{\"percentile\":{},\"value\":{}}}",
+            json_number(*p),
+            json_number(*value),
+        ).unwrap();
+    }
+
+    write!(
+        json,
+        "],\"most\":{},\"fewest\":{},\"normalized\":{{\"per_log2_factorial\":{},\
+        \"per_n_log2_n\":{},\"per_n\":{}}}}}",
+        metric.most,
+        metric.fewest,
+        metric.normalized.per_log2_factorial.map_or("null".to_string(), json_number),
+        metric.normalized.per_n_log2_n.map_or("null".to_string(), json_number),
+        metric.normalized.per_n.map_or("null".to_string(), json_number),
+    ).unwrap();
+    json
+}
+
+fn difference_json(difference: &Difference, confidence: f64) -> String {
+    format!(
+        "{{\"count\":{},\"average\":{},\"stddev\":{},\"sample_stddev\":{},\
+        \"confidence_interval\":{},\"wins\":{},\"ties\":{},\"win_rate\":{},\
+        \"t_test_p_value\":{},\"wilcoxon_p_value\":{}}}",
+        difference.count,
+        json_number(difference.average),
+        json_number(difference.stddev),
+        json_number(difference.sample_stddev),
+        json_interval(difference.interval(confidence)),
+        difference.wins,
+        difference.ties,
+        json_number(difference.win_rate()),
+        json_number(difference.t_test_p_value()),
+        json_number(difference.wilcoxon_p_value),
+    )
+}
+
+fn json_interval(interval: Option<(f64, f64)>) -> String {
+    match interval {
+        Some((low, high)) => format!("[{},{}]", json_number(low), json_number(high)),
+        None => "null".to_string(),
+    }
+}
+
+//JSON has no NaN or infinity.
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+fn csv_row(fields: &[String]) -> String {
+    let mut row = fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+
+    row.push('\n');
+    row
+}
+
+fn gaps_text(gaps: &[usize]) -> String {
+    gaps.iter().map(|gap| gap.to_string()).collect::<Vec<String>>().join(" ")
+}
+
+fn optional(value: Option<f64>) -> String {
+    value.map_or(String::new(), |value| value.to_string())
+}
+
+fn optional_pair(pair: Option<(f64, f64)>) -> (String, String) {
+    match pair {
+        Some((low, high)) => (low.to_string(), high.to_string()),
+        None => (String::new(), String::new()),
+    }
+}
+
+fn markdown_header(columns: &[String]) -> String {
+    let mut header = markdown_row(columns);
+    header.push_str(&markdown_row(&vec!["---".to_string(); columns.len()]));
+    header
+}
+
+fn markdown_row(cells: &[String]) -> String {
+    format!("| {} |\n", cells.join(" | "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::{
+        perform_rounds, Distribution, QuicksortSorter, ReportSettings, RoundMode,
+        ShellsortSorter, Sorter,
+    };
+
+    fn run_entries(experiment: &Experiment, settings: &ReportSettings) -> Vec<Entry> {
+        let shellsort = ShellsortSorter { gaps: vec![4, 1] };
+        let sorters: Vec<&dyn Sorter> = vec![&shellsort];
+
+        perform_rounds(experiment, &sorters, true)
+            .unwrap()
+            .iter()
+            .map(|results| Entry {
+                gaps: Some(vec![4, 1]),
+                report: SorterReport::new(results, settings).unwrap(),
+                paired: None,
+            })
+            .collect()
+    }
+
+    fn experiment() -> Experiment {
+        Experiment {
+            length: 20,
+            seed: 3,
+            rounds: 10,
+            distribution: Distribution::Uniform,
+            mode: RoundMode::Independent,
+        }
+    }
+
+    #[test]
+    fn test_format_from_str() {
+        for format in [Format::Text, Format::Json, Format::Csv, Format::Markdown] {
+            assert_eq!(Format::from_str(&format.to_string()), Ok(format));
+        }
+
+        assert!(Format::from_str("xml").is_err());
+    }
+
+    #[test]
+    fn test_json() {
+        let experiment = experiment();
+        let settings = ReportSettings {
+            percentiles: vec![90.0],
+            confidence: 0.95,
+            length: Some(20),
+        };
+        let run = Run {
+            experiment: &experiment,
+            confidence: settings.confidence,
+            floats: None,
+            entries: run_entries(&experiment, &settings),
+        };
+
+        let json = run.to_json();
+        assert!(json.starts_with(
+            "{\"parameters\":{\"length\":20,\"rounds\":10,\"seed\":3,\
+            \"distribution\":\"uniform\",\"round_mode\":\"independent\",\"confidence\":0.95},"
+        ), "{}", json);
+        assert!(json.contains("\"sorter\":\"Shellsort\",\"gaps\":[4, 1],"), "{}", json);
+        assert!(json.contains("\"percentiles\":[{\"percentile\":90,\"value\":"), "{}", json);
+        assert!(json.contains("\"paired\":null"), "{}", json);
+        assert!(json.contains(",\"per_n_log2_n\":null,\"per_n\":"), "moves per n: {}", json);
+        assert_eq!(json.matches('{').count(), json.matches('}').count(), "balanced braces");
+    }
+
+    #[test]
+    fn test_csv() {
+        let experiment = experiment();
+        let settings = ReportSettings {
+            percentiles: vec![5.0, 95.0],
+            confidence: 0.9,
+            length: None,
+        };
+        let run = Run {
+            experiment: &experiment,
+            confidence: settings.confidence,
+            floats: None,
+            entries: run_entries(&experiment, &settings),
+        };
+
+        let csv = run.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        let columns = lines[0].split(',').count();
+
+        assert_eq!(lines.len(), 3, "header and one row per metric");
+        assert!(lines[0].contains(",median,p5,p95,most,"), "{}", lines[0]);
+        assert!(lines[1].starts_with("20,10,3,uniform,independent,0.9,Shellsort,4 1,comparisons,10,"));
+        assert!(lines.iter().all(|line| line.split(',').count() == columns), "same columns");
+    }
+
+    #[test]
+    fn test_rounds() {
+        let experiment = experiment();
+        let shellsort = ShellsortSorter { gaps: vec![4, 1] };
+        let quicksort = QuicksortSorter;
+        let sorters: Vec<&dyn Sorter> = vec![&shellsort, &quicksort];
+        let results = perform_rounds(&experiment, &sorters, true).unwrap();
+        let gaps = [4, 1];
+        let sorters = [(Some(&gaps[..]), &results[0]), (None, &results[1])];
+
+        let mut csv: Vec<u8> = Vec::new();
+        write_rounds_csv(&mut csv, 3, &sorters).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0], "round,seed,sorter,gaps,comparisons,moves,swaps,max_depth");
+        assert_eq!(lines.len(), 21, "header and 10 rounds of 2 sorters");
+        assert_eq!(
+            lines[3],
+            format!(
+                "2,{},Shellsort,4 1,{},{},,",
+                round_seed(3, 2),
+                results[0].accumulators[0].values().unwrap()[2],
+                results[0].accumulators[1].values().unwrap()[2],
+            ),
+        );
+        assert!(lines[11].starts_with("0,") && lines[11].contains(",Quicksort,,"), "{}", lines[11]);
+
+        let mut json: Vec<u8> = Vec::new();
+        write_rounds_json_lines(&mut json, 3, &sorters).unwrap();
+        let json = String::from_utf8(json).unwrap();
+
+        assert_eq!(json.lines().count(), 20);
+        assert!(json.lines().nth(10).unwrap().starts_with(&format!(
+            "{{\"round\":0,\"seed\":{},\"sorter\":\"Quicksort\",\"gaps\":null,\"comparisons\":",
+            round_seed(3, 0),
+        )));
+        assert!(json.contains(",\"max_depth\":"));
+    }
+
+    #[test]
+    fn test_rounds_need_values() {
+        let results = SorterResults::new(&QuicksortSorter, false);
+
+        assert!(write_rounds_csv(&mut Vec::new(), 0, &[(None, &results)]).is_err());
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(json_string("a \"b\"\\\n"), "\"a \\\"b\\\"\\\\\\n\"");
+        assert_eq!(json_number(f64::NAN), "null");
+        assert_eq!(
+            csv_row(&["a,b".to_string(), "say \"hi\"".to_string()]),
+            "\"a,b\",\"say \"\"hi\"\"\"\n",
+        );
+    }
+}
@@ -0,0 +1,429 @@
+use std::{fs, path::PathBuf};
+
+use crate::{
+    chart::{histogram_chart, log_log_chart, Series},
+    compare::{compare_sequences, CompareParameters, Ranking},
+    optimize::{optimize, Objective, SearchParameters},
+    output::{write_rounds, Entry, Format, Run},
+    settings::{Mode, Settings},
+    sort::{
+        perform_exhaustive, perform_float_rounds, perform_rounds, perform_rounds_parallel,
+        FloatKeys, FloatValues, PairedReport, QuicksortSorter, ReportError, ShellsortSorter,
+        Sorter, SorterReport, SorterResults,
+    },
+    sweep::{perform_sweep, Lengths, SweepParameters},
+};
+
+//Performs what the settings ask for and prints the reports. Progress of long
+//searches and resumed checkpoints is handed to progress instead, to be shown
+//apart from the reports.
+pub fn run(settings: &Settings, mut progress: impl FnMut(&str)) -> Result<(), String> {
+    match settings.mode()? {
+        Mode::Exhaustive => exhaustive(settings),
+        Mode::Sweep(lengths) => sweep(settings, &lengths),
+        Mode::Compare => compare(settings, &mut progress),
+        Mode::Single => single(settings, &mut progress),
+    }
+}
+
+fn single(settings: &Settings, progress: &mut dyn FnMut(&str)) -> Result<(), String> {
+    let experiment = settings.experiment()?;
+    let report_settings = settings.report_settings()?;
+
+    let gaps: Vec<usize> = if !settings.optimize {
+        settings.gap_sequence()?.to_vec(settings.length)
+    } else {
+        let objective = if settings.optimize_moves {
+            Objective::Moves
+        } else {
+            Objective::Comparisons
+        };
+
+        progress(&format!(
+            "Searching for the gap sequence with fewest average {} on array \
+            of length {} using {} round(s) per candidate.",
+            objective.name(),
+            settings.length,
+            settings.rounds,
+        ));
+
+        let gaps = optimize(
+            &SearchParameters {
+                experiment: experiment.clone(),
+                objective,
+                candidates: settings.candidates,
+                initial_rounds: settings.initial_rounds,
+                threads: settings.threads,
+            },
+            settings.checkpoint().as_ref(),
+            |step| progress(&step.to_string()),
+        )?;
+
+        progress("");
+        gaps
+    };
+
+    let floats = settings.floats();
+
+    if settings.format == Format::Text {
+        //Float values other than the permutation do not come from the input.
+        let input = if floats && settings.float_values != FloatValues::Permutation {
+            settings.float_values.describe().to_string()
+        } else {
+            experiment.distribution.describe()
+        };
+
+        println!(
+            "Sorting results on array of length {} for {} round(s) of {}.",
+            experiment.length,
+            experiment.rounds,
+            input,
+        );
+        println!("Shellsort performed with gap sequence: {:?}", gaps);
+
+        if floats {
+            println!(
+                "Values sorted as f64 with {:.1}% NaN, placed {}.",
+                settings.nan_fraction * 100.0,
+                settings.nan_placement,
+            );
+        }
+        println!();
+    }
+
+    let shellsort = ShellsortSorter { gaps: gaps.clone() };
+    let mut sorters: Vec<&dyn Sorter> = vec![&shellsort];
+    if settings.quicksort {
+        sorters.push(&QuicksortSorter);
+    }
+
+    let keep_values = settings.keep_values();
+
+    let keys = FloatKeys {
+        values: settings.float_values,
+        nan_fraction: settings.nan_fraction,
+        placement: settings.nan_placement,
+    };
+
+    let results = if floats {
+        perform_float_rounds(&experiment, &gaps, settings.quicksort, &keys, keep_values)
+    } else if settings.threads > 0 {
+        perform_rounds_parallel(&experiment, &sorters, keep_values, settings.threads)
+    } else {
+        perform_rounds(&experiment, &sorters, keep_values)
+    }?;
+
+    if !settings.rounds_output.is_empty() {
+        //Shellsort is always the first sorter.
+        let sorters: Vec<(Option<&[usize]>, &SorterResults)> = results
+            .iter()
+            .enumerate()
+            .map(|(i, results)| (if i == 0 { Some(&gaps[..]) } else { None }, results))
+            .collect();
+
+        write_rounds(&PathBuf::from(&settings.rounds_output), experiment.seed, &sorters)?;
+    }
+
+    if !settings.chart.is_empty() {
+        let names: Vec<String> = results.iter().map(|results| results.name.clone()).collect();
+        write_histogram(settings, &names, &results)?;
+    }
+
+    if settings.format != Format::Text {
+        let entries = results
+            .iter()
+            .enumerate()
+            .map(|(i, results)| Ok(Entry {
+                gaps: if i == 0 { Some(gaps.clone()) } else { None },
+                report: SorterReport::new(results, &report_settings)?,
+                paired: None,
+            }))
+            .collect::<Result<Vec<Entry>, ReportError>>()
+            .map_err(|e| e.to_string())?;
+
+        return print_run(settings, &Run {
+            experiment: &experiment,
+            confidence: report_settings.confidence,
+            floats: if floats { Some(&keys) } else { None },
+            entries,
+        });
+    }
+
+    for results in results.iter() {
+        match SorterReport::new(results, &report_settings) {
+            Ok(report) => {
+                report.print();
+                println!();
+            },
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+
+    Ok(())
+}
+
+fn compare(settings: &Settings, progress: &mut dyn FnMut(&str)) -> Result<(), String> {
+    let experiment = settings.experiment()?;
+    let report_settings = settings.report_settings()?;
+
+    let sequences = settings.compare_candidates()?;
+    let candidates: Vec<Vec<usize>> = sequences
+        .iter()
+        .map(|gap_sequence| gap_sequence.to_vec(experiment.length))
+        .collect();
+
+    if settings.format == Format::Text {
+        println!(
+            "Comparing {} gap sequences on the same arrays of length {} for {} round(s) \
+            of {}.",
+            candidates.len(),
+            experiment.length,
+            experiment.rounds,
+            experiment.distribution.describe(),
+        );
+        println!("Differences are relative to gap sequence: {:?}", candidates[0]);
+        println!();
+    }
+
+    let results = compare_sequences(
+        &CompareParameters {
+            experiment: experiment.clone(),
+            checkpoint_interval: settings.checkpoint_interval,
+            threads: settings.threads,
+            keep_values: settings.keep_values(),
+        },
+        &candidates,
+        settings.checkpoint().as_ref(),
+        |completed| progress(&format!("Resuming comparison after {} round(s).", completed)),
+    )?;
+    let sorter_results = &results.results;
+
+    if !settings.rounds_output.is_empty() {
+        let sorters: Vec<(Option<&[usize]>, &SorterResults)> = candidates
+            .iter()
+            .map(|gaps| &gaps[..])
+            .map(Some)
+            .zip(sorter_results.iter())
+            .collect();
+
+        write_rounds(&PathBuf::from(&settings.rounds_output), experiment.seed, &sorters)?;
+    }
+
+    if !settings.chart.is_empty() {
+        let names: Vec<String> = sequences.iter().map(|sequence| sequence.to_string()).collect();
+        write_histogram(settings, &names, sorter_results)?;
+    }
+
+    if settings.format != Format::Text {
+        let entries = candidates
+            .iter()
+            .zip(results.differences.iter())
+            .zip(sorter_results.iter())
+            .enumerate()
+            .map(|(i, ((gaps, differences), sorter_results))| Ok(Entry {
+                gaps: Some(gaps.clone()),
+                report: SorterReport::new(sorter_results, &report_settings)?,
+                paired: if i > 0 {
+                    Some(PairedReport::new(differences, &report_settings)?)
+                } else {
+                    None
+                },
+            }))
+            .collect::<Result<Vec<Entry>, ReportError>>()
+            .map_err(|e| e.to_string())?;
+
+        return print_run(settings, &Run {
+            experiment: &experiment,
+            confidence: report_settings.confidence,
+            floats: None,
+            entries,
+        });
+    }
+
+    let candidate_results = candidates
+        .iter()
+        .zip(results.differences.iter())
+        .zip(sorter_results.iter());
+
+    let mut reports: Vec<SorterReport> = Vec::new();
+
+    for (i, ((gaps, differences), sorter_results)) in candidate_results.enumerate() {
+        println!("Shellsort performed with gap sequence: {:?}", gaps);
+
+        match SorterReport::new(sorter_results, &report_settings) {
+            Ok(report) => {
+                report.print();
+                reports.push(report);
+            },
+            Err(e) => eprintln!("{}", e),
+        }
+
+        if i > 0 {
+            match PairedReport::new(differences, &report_settings) {
+                Ok(report) => report.print(),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+
+        println!();
+    }
+
+    if candidates.len() > 1 && reports.len() == candidates.len() {
+        let ranked: Vec<(String, &[usize], &SorterReport)> = sequences
+            .iter()
+            .zip(candidates.iter())
+            .zip(reports.iter())
+            .map(|((sequence, gaps), report)| (sequence.to_string(), &gaps[..], report))
+            .collect();
+
+        Ranking::new(experiment.length, &ranked)?.print();
+    }
+
+    Ok(())
+}
+
+fn sweep(settings: &Settings, lengths: &Lengths) -> Result<(), String> {
+    let parameters = SweepParameters {
+        experiment: settings.experiment()?,
+        max_distance: settings.max_distance,
+        quicksort: settings.quicksort,
+        threads: settings.threads,
+    };
+    let sequences = settings.sequences()?;
+
+    println!(
+        "Sweeping {} length(s) from {} to {} for {} round(s) of {} each.",
+        lengths.0.len(),
+        lengths.0[0],
+        lengths.0[lengths.0.len() - 1],
+        parameters.experiment.rounds,
+        parameters.describe(),
+    );
+    println!();
+
+    let results = perform_sweep(&parameters, lengths, &sequences)?;
+
+    for series in results.iter() {
+        series.print();
+        println!();
+    }
+
+    if !settings.chart.is_empty() {
+        let series: Vec<Series> = results
+            .iter()
+            .map(|series| Series {
+                name: series.name.clone(),
+                points: series.comparisons(),
+            })
+            .collect();
+
+        let svg = log_log_chart(
+            &format!("Average comparisons, {}", parameters.describe()),
+            "Length",
+            "Comparisons",
+            &series,
+        );
+        write_chart(settings, svg)?;
+    }
+
+    Ok(())
+}
+
+fn exhaustive(settings: &Settings) -> Result<(), String> {
+    let candidates: Vec<Vec<usize>> = settings.sequences()?
+        .iter()
+        .map(|gap_sequence| gap_sequence.to_vec(settings.length))
+        .collect();
+
+    let results = perform_exhaustive(settings.length, &candidates, settings.quicksort)?;
+
+    println!(
+        "Exact sorting results over all {} permutations of length {}.",
+        results.permutations,
+        settings.length,
+    );
+    println!();
+
+    for (gaps, report) in candidates.iter().zip(results.shellsort.iter()) {
+        println!("Shellsort performed with gap sequence: {:?}", gaps);
+        report.print(settings.length);
+        println!();
+    }
+
+    if let Some(report) = results.quicksort {
+        report.print(settings.length);
+        println!();
+    }
+
+    Ok(())
+}
+
+//The comparisons of every round of each sorter, in one histogram.
+fn write_histogram(
+    settings: &Settings,
+    names: &[String],
+    results: &[SorterResults],
+) -> Result<(), String> {
+    let series: Vec<(String, &[u64])> = names
+        .iter()
+        .zip(results.iter())
+        .filter_map(|(name, results)| {
+            let comparisons = results.metrics.iter().position(|&metric| metric == "comparisons")?;
+            let values = results.accumulators[comparisons].values()?;
+            Some((name.clone(), values))
+        })
+        .collect();
+
+    let svg = histogram_chart(
+        &format!("Comparisons per round, length {}", settings.length),
+        "Comparisons",
+        &series,
+        40,
+    );
+    write_chart(settings, svg)
+}
+
+fn write_chart(settings: &Settings, svg: String) -> Result<(), String> {
+    fs::write(&settings.chart, svg).map_err(|e| format!("Could not write chart: {}", e))
+}
+
+fn print_run(settings: &Settings, run: &Run) -> Result<(), String> {
+    print!("{}", run.render(settings.format)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_settings_are_errors() {
+        let settings = Settings {
+            compare: "ciura_2001;not a sequence".to_string(),
+            ..Settings::default()
+        };
+        assert!(run(&settings, |_| ()).is_err());
+
+        let settings = Settings { input: "triangle".to_string(), ..Settings::default() };
+        assert!(run(&settings, |_| ()).is_err());
+
+        let settings = Settings { percentiles: "a".to_string(), ..Settings::default() };
+        assert!(run(&settings, |_| ()).is_err());
+    }
+
+    #[test]
+    fn test_optimize_reports_progress() {
+        let settings = Settings {
+            optimize: true,
+            length: 30,
+            rounds: 4,
+            ..Settings::default()
+        };
+
+        let mut messages: Vec<String> = Vec::new();
+        run(&settings, |message| messages.push(message.to_string())).unwrap();
+
+        assert!(messages[0].starts_with("Searching for the gap sequence"), "{}", messages[0]);
+        assert!(messages[1].starts_with("Gap sequence [1"), "{}", messages[1]);
+    }
+}
@@ -0,0 +1,477 @@
+use std::{path::PathBuf, str::FromStr};
+
+use crate::{
+    checkpoint::CheckpointFile,
+    gap_sequences::GapSequence,
+    output::Format,
+    sort::{
+        Distribution, Experiment, FloatValues, MaxDistance, NanPlacement, ReportSettings,
+        RoundMode,
+    },
+    sweep::Lengths,
+};
+
+//Everything the command line can ask for, as given. The methods check the
+//settings and turn them into what the library runs with.
+pub struct Settings {
+    pub seed: u64,
+    pub rounds: usize,
+    pub length: usize,
+    pub threads: usize,
+    pub gap_sequence: String,
+    pub compare: String,
+    pub optimize: bool,
+    pub optimize_moves: bool,
+    pub candidates: usize,
+    pub initial_rounds: usize,
+    pub quicksort: bool,
+    pub exhaustive: bool,
+    pub input: String,
+    pub max_distance: MaxDistance,
+    pub probability: f64,
+    pub floats: bool,
+    pub float_values: FloatValues,
+    pub nan_fraction: f64,
+    pub nan_placement: NanPlacement,
+    pub round_mode: RoundMode,
+    pub checkpoint: String,
+    pub resume: bool,
+    pub checkpoint_interval: usize,
+    pub percentiles: String,
+    pub confidence: f64,
+    pub format: Format,
+    pub rounds_output: String,
+    pub sweep: String,
+    pub chart: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            rounds: 100,
+            length: 100,
+            threads: 0,
+            gap_sequence: String::from(""),
+            compare: String::from(""),
+            optimize: false,
+            optimize_moves: false,
+            candidates: 16,
+            initial_rounds: 10,
+            quicksort: false,
+            exhaustive: false,
+            input: String::from(""),
+            max_distance: MaxDistance::Length,
+            probability: 1.0,
+            floats: false,
+            float_values: FloatValues::Permutation,
+            nan_fraction: 0.0,
+            nan_placement: NanPlacement::Last,
+            round_mode: RoundMode::Independent,
+            checkpoint: String::from(""),
+            resume: false,
+            checkpoint_interval: 10,
+            percentiles: String::from(""),
+            confidence: 0.95,
+            format: Format::Text,
+            rounds_output: String::from(""),
+            sweep: String::from(""),
+            chart: String::from(""),
+        }
+    }
+}
+
+//What a run does, from the settings that select it.
+pub enum Mode {
+    Exhaustive,
+    Sweep(Lengths),
+    Compare,
+    Single,
+}
+
+impl Settings {
+    //Fails on settings that cannot be combined, rather than ignoring some.
+    pub fn mode(&self) -> Result<Mode, String> {
+        //Only a single run sorts f64 values, the other modes sort integers.
+        if self.floats() {
+            if self.threads > 0 {
+                return Err("'--floats' cannot be used with '-t'.".to_string());
+            }
+
+            if self.exhaustive
+                || self.optimize
+                || !self.sweep.is_empty()
+                || !self.compare.is_empty()
+            {
+                return Err(
+                    "'--floats', '--float-values' and '--nan-fraction' cannot be used with \
+                    '--exhaustive', '-o', '--sweep' or '--compare'."
+                        .to_string(),
+                );
+            }
+
+            if self.float_values != FloatValues::Permutation
+                && (!self.input.is_empty()
+                    || self.max_distance != MaxDistance::Length
+                    || self.probability != 1.0
+                    || self.round_mode != RoundMode::Independent)
+            {
+                return Err(
+                    "'-i', '-d', '-p' and '--round-mode' can only be used with \
+                    '--float-values permutation'."
+                        .to_string(),
+                );
+            }
+        }
+
+        //Options left at their defaults cannot be told apart from ones not
+        //given, so only other values are errors.
+        let default = Settings::default();
+
+        if !self.optimize
+            && (self.optimize_moves
+                || self.candidates != default.candidates
+                || self.initial_rounds != default.initial_rounds)
+        {
+            return Err("'-m', '-c' and '--initial-rounds' can only be used with '-o'.".to_string());
+        }
+
+        if self.resume && self.checkpoint.is_empty() {
+            return Err("'--resume' cannot be used without '--checkpoint'.".to_string());
+        }
+
+        if self.checkpoint_interval != default.checkpoint_interval
+            && (self.compare.is_empty() || self.checkpoint.is_empty())
+        {
+            return Err(
+                "'--checkpoint-interval' can only be used with '--compare' and '--checkpoint'."
+                    .to_string(),
+            );
+        }
+
+        if self.exhaustive {
+            if self.optimize || self.threads > 0 {
+                return Err("'--exhaustive' cannot be used with '-o' or '-t'.".to_string());
+            }
+
+            if self.format != Format::Text
+                || !self.rounds_output.is_empty()
+                || !self.chart.is_empty()
+                || !self.checkpoint.is_empty()
+            {
+                return Err(
+                    "'--exhaustive' cannot be used with '--format', '--rounds-output', '--chart' \
+                    or '--checkpoint'."
+                        .to_string(),
+                );
+            }
+
+            return Ok(Mode::Exhaustive);
+        }
+
+        if !self.sweep.is_empty() {
+            if self.optimize
+                || self.format != Format::Text
+                || !self.rounds_output.is_empty()
+                || !self.checkpoint.is_empty()
+            {
+                return Err(
+                    "'--sweep' cannot be used with '-o', '--format', '--rounds-output' or \
+                    '--checkpoint'."
+                        .to_string(),
+                );
+            }
+
+            return Lengths::from_str(&self.sweep).map(Mode::Sweep);
+        }
+
+        if !self.compare.is_empty() {
+            if self.optimize || self.quicksort {
+                return Err("'--compare' cannot be used with '-o' or '-q'.".to_string());
+            }
+
+            return Ok(Mode::Compare);
+        }
+
+        //Only searches and comparisons save checkpoints.
+        if !self.optimize && !self.checkpoint.is_empty() {
+            return Err("'--checkpoint' can only be used with '-o' or '--compare'.".to_string());
+        }
+
+        Ok(Mode::Single)
+    }
+
+    pub fn floats(&self) -> bool {
+        self.floats || self.nan_fraction > 0.0 || self.float_values != FloatValues::Permutation
+    }
+
+    //Medians, percentiles, the rounds file and the histogram need the value
+    //of every round.
+    pub fn keep_values(&self) -> bool {
+        !self.percentiles.trim().is_empty()
+            || !self.rounds_output.is_empty()
+            || !self.chart.is_empty()
+    }
+
+    pub fn report_settings(&self) -> Result<ReportSettings, String> {
+        let percentiles = self.percentiles
+            .split(',')
+            .filter(|p| !p.trim().is_empty())
+            .map(|p| {
+                p.trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|p| (0.0..=100.0).contains(p))
+                    .ok_or(format!("Invalid percentile '{}'.", p.trim()))
+            })
+            .collect::<Result<Vec<f64>, String>>()?;
+
+        let settings = ReportSettings {
+            percentiles,
+            confidence: self.confidence,
+            length: Some(self.length),
+        };
+        settings.check().map_err(|e| e.to_string())?;
+
+        Ok(settings)
+    }
+
+    pub fn gap_sequence(&self) -> Result<GapSequence, String> {
+        GapSequence::from_str(&self.gap_sequence)
+            .map_err(|_| format!("Invalid gap sequence '{}'.", self.gap_sequence))
+    }
+
+    //The sequences of '--compare' separated by ';', where 'all' stands for
+    //every named sequence. Empty sequences between separators are skipped.
+    pub fn compare_candidates(&self) -> Result<Vec<GapSequence>, String> {
+        let mut sequences = Vec::new();
+
+        for gap_sequence in self.compare.split(';').map(str::trim) {
+            if gap_sequence.is_empty() {
+                continue;
+            }
+
+            if gap_sequence.to_lowercase() == "all" {
+                sequences.extend(GapSequence::all());
+            } else {
+                sequences.push(
+                    GapSequence::from_str(gap_sequence)
+                        .map_err(|_| format!("Invalid gap sequence '{}'.", gap_sequence))?
+                );
+            }
+        }
+
+        if sequences.is_empty() {
+            return Err(format!("No gap sequences to compare in '{}'.", self.compare));
+        }
+
+        Ok(sequences)
+    }
+
+    //The sequences of '--compare', or the one of '-g' without it.
+    pub fn sequences(&self) -> Result<Vec<GapSequence>, String> {
+        if self.compare.is_empty() {
+            Ok(vec![self.gap_sequence()?])
+        } else {
+            self.compare_candidates()
+        }
+    }
+
+    //Bounded swaps with '-d' and '-p' stay the default input. Uniformly
+    //random permutations are picked with '-i uniform'.
+    pub fn distribution(&self) -> Result<Distribution, String> {
+        let bounded_swaps = Distribution::BoundedSwaps {
+            max_distance: self.max_distance.at(self.length),
+            probability: self.probability,
+        };
+
+        match self.input.trim().to_lowercase().as_str() {
+            "" | "bounded_swaps" => Ok(bounded_swaps),
+            input => Distribution::from_str(input),
+        }
+    }
+
+    pub fn experiment(&self) -> Result<Experiment, String> {
+        Ok(Experiment {
+            length: self.length,
+            seed: self.seed,
+            rounds: self.rounds,
+            distribution: self.distribution()?,
+            mode: self.round_mode,
+        })
+    }
+
+    pub fn checkpoint(&self) -> Option<CheckpointFile> {
+        if self.checkpoint.is_empty() {
+            return None;
+        }
+
+        Some(CheckpointFile {
+            path: PathBuf::from(&self.checkpoint),
+            resume: self.resume,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_candidates() {
+        let settings = Settings {
+            compare: "ciura_2001; ;1,4,13;".to_string(),
+            ..Settings::default()
+        };
+        assert_eq!(
+            settings.compare_candidates(),
+            Ok(vec![GapSequence::Ciura2001, GapSequence::Custom(vec![1, 4, 13])]),
+            "empty sequences are skipped",
+        );
+
+        let settings = Settings { compare: ";".to_string(), ..Settings::default() };
+        assert!(settings.compare_candidates().is_err(), "nothing to compare");
+
+        let settings = Settings { compare: "ciura_2001;x".to_string(), ..Settings::default() };
+        assert!(settings.compare_candidates().is_err());
+    }
+
+    #[test]
+    fn test_invalid_combinations() {
+        let settings = Settings {
+            exhaustive: true,
+            chart: "chart.svg".to_string(),
+            ..Settings::default()
+        };
+        assert!(settings.mode().is_err());
+
+        let settings = Settings { exhaustive: true, threads: 2, ..Settings::default() };
+        assert!(settings.mode().is_err(), "permutations are sorted on one thread");
+
+        let settings = Settings {
+            sweep: "10..100".to_string(),
+            rounds_output: "rounds.csv".to_string(),
+            ..Settings::default()
+        };
+        assert!(settings.mode().is_err());
+
+        let settings = Settings {
+            sweep: "10..100".to_string(),
+            checkpoint: "sweep.checkpoint".to_string(),
+            ..Settings::default()
+        };
+        assert!(settings.mode().is_err(), "sweeps are not checkpointed");
+
+        let settings = Settings { sweep: "100..10".to_string(), ..Settings::default() };
+        assert!(settings.mode().is_err(), "invalid lengths");
+
+        let settings = Settings {
+            exhaustive: true,
+            checkpoint: "exhaustive.checkpoint".to_string(),
+            ..Settings::default()
+        };
+        assert!(settings.mode().is_err(), "permutations are not checkpointed");
+
+        let compare = || Settings { compare: "ciura_2001".to_string(), ..Settings::default() };
+        assert!(Settings { optimize: true, ..compare() }.mode().is_err(), "nothing to search");
+        assert!(Settings { quicksort: true, ..compare() }.mode().is_err(), "only gap sequences");
+        assert!(Settings { optimize_moves: true, ..compare() }.mode().is_err());
+        assert!(Settings { candidates: 4, ..compare() }.mode().is_err());
+        assert!(Settings { initial_rounds: 2, ..compare() }.mode().is_err());
+        assert!(matches!(Settings { threads: 2, ..compare() }.mode(), Ok(Mode::Compare)));
+
+        let settings = Settings { checkpoint: "run.checkpoint".to_string(), ..Settings::default() };
+        assert!(settings.mode().is_err(), "a single run is not checkpointed");
+        assert!(
+            matches!(Settings { optimize: true, ..settings }.mode(), Ok(Mode::Single)),
+            "searches are checkpointed",
+        );
+
+        let settings = Settings { resume: true, ..Settings::default() };
+        assert!(settings.mode().is_err(), "nothing to resume from");
+
+        let settings = Settings {
+            optimize: true,
+            checkpoint: "search.checkpoint".to_string(),
+            checkpoint_interval: 5,
+            ..Settings::default()
+        };
+        assert!(settings.mode().is_err(), "searches save a checkpoint after every batch");
+
+        assert!(matches!(Settings::default().mode(), Ok(Mode::Single)));
+    }
+
+    #[test]
+    fn test_floats_only_in_single_runs() {
+        let settings = Settings { floats: true, ..Settings::default() };
+        assert!(matches!(settings.mode(), Ok(Mode::Single)));
+
+        let settings = Settings { floats: true, threads: 2, ..Settings::default() };
+        assert!(settings.mode().is_err(), "floats are sorted on one thread");
+
+        let settings = Settings {
+            nan_fraction: 0.1,
+            compare: "ciura_2001".to_string(),
+            ..Settings::default()
+        };
+        assert!(settings.mode().is_err(), "a NaN fraction implies floats");
+
+        let settings = Settings { floats: true, optimize: true, ..Settings::default() };
+        assert!(settings.mode().is_err());
+
+        let settings = Settings {
+            floats: true,
+            sweep: "10..100".to_string(),
+            ..Settings::default()
+        };
+        assert!(settings.mode().is_err());
+
+        let settings = Settings { floats: true, exhaustive: true, ..Settings::default() };
+        assert!(settings.mode().is_err());
+
+        let settings = Settings { float_values: FloatValues::Uniform, ..Settings::default() };
+        assert!(settings.floats(), "other float values imply floats");
+        assert!(matches!(settings.mode(), Ok(Mode::Single)));
+
+        let settings = Settings {
+            float_values: FloatValues::Specials,
+            input: "reversed".to_string(),
+            ..Settings::default()
+        };
+        assert!(settings.mode().is_err(), "the values do not come from the input");
+    }
+
+    #[test]
+    fn test_report_settings() {
+        let settings = Settings { percentiles: "5, 95".to_string(), ..Settings::default() };
+        assert_eq!(settings.report_settings().unwrap().percentiles, vec![5.0, 95.0]);
+
+        let settings = Settings { percentiles: "5,101".to_string(), ..Settings::default() };
+        assert!(settings.report_settings().is_err());
+
+        let settings = Settings { confidence: 1.5, ..Settings::default() };
+        assert!(settings.report_settings().is_err());
+    }
+
+    #[test]
+    fn test_distribution() {
+        assert!(
+            Settings::default().distribution()
+                == Ok(Distribution::BoundedSwaps { max_distance: 100.0, probability: 1.0 }),
+            "bounded swaps by default",
+        );
+
+        let settings = Settings { input: "uniform".to_string(), ..Settings::default() };
+        assert!(matches!(settings.distribution(), Ok(Distribution::Uniform)));
+
+        let settings = Settings { probability: 0.5, length: 40, ..Settings::default() };
+        assert!(
+            settings.distribution()
+                == Ok(Distribution::BoundedSwaps { max_distance: 40.0, probability: 0.5 }),
+            "the length is the default distance",
+        );
+
+        let settings = Settings { input: "triangle".to_string(), ..Settings::default() };
+        assert!(settings.distribution().is_err());
+    }
+}
@@ -1,63 +1,140 @@
-use std::cmp::{max, min};
+use std::{fmt, str::FromStr, thread};
 
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand::{rngs::StdRng, SeedableRng};
 
-use self::{
-    quicksort::{
-        quicksort, QuicksortReport, QuicksortResult
-    }, 
+pub use self::{
+    counted::Counted,
+    distribution::{Distribution, MaxDistance},
+    exhaustive::{
+        perform_exhaustive, ExhaustiveQuicksort, ExhaustiveResults, ExhaustiveShellsort,
+        Histogram, MAX_EXHAUSTIVE_LENGTH,
+    },
+    float::{
+        perform_float_rounds, quicksort_f64, shellsort_f64, FloatKeys, FloatValues, NanPlacement,
+    },
+    metrics::{percentile, Accumulator, MetricReport, Normalized, ReportSettings},
+    paired::{
+        format_p_value, Difference, DifferenceAccumulator, PairedDifferences, PairedReport,
+        PairedResults,
+    },
+    quicksort::{quicksort, QuicksortResult, QuicksortSorter},
+    shared_passes::shellsort_shared,
     shellsort::{
-        shellsort, ShellsortReport, ShellsortResult
-    }
+        shellsort, shellsort_by, shellsort_by_key, shellsort_pass, ShellsortResult,
+        ShellsortSorter, SHELLSORT_METRICS,
+    },
+    sorter::{Sorter, SorterReport, SorterResults},
+    statistics::{
+        confidence_margin, log2_factorial, normal_cdf, student_t_cdf, student_t_quantile,
+    },
 };
 
 mod shellsort;
 mod quicksort;
+mod paired;
+mod shared_passes;
+mod distribution;
+mod counted;
+mod exhaustive;
+mod float;
+mod sorter;
+mod metrics;
+mod statistics;
+
+//Cumulative rounds shuffle the array left by the previous round, so partial
+//shuffles drift towards fully random arrays. Independent rounds restart
+//from the sorted array before every shuffle.
+#[derive(Clone, Copy, PartialEq)]
+pub enum RoundMode {
+    Cumulative,
+    Independent,
+}
+
+impl fmt::Display for RoundMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoundMode::Cumulative => write!(f, "cumulative"),
+            RoundMode::Independent => write!(f, "independent"),
+        }
+    }
+}
+
+impl FromStr for RoundMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "" | "independent" => Ok(RoundMode::Independent),
+            "cumulative" => Ok(RoundMode::Cumulative),
+            _ => Err("Invalid round mode provided.".to_string()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Experiment {
+    pub length: usize,
+    pub seed: u64,
+    pub rounds: usize,
+    pub distribution: Distribution,
+    pub mode: RoundMode,
+}
+
+//Mixes the round index into the base seed (SplitMix64), so every round gets
+//its own generator no matter which thread performs it.
+pub fn round_seed(seed: u64, round: usize) -> u64 {
+    let mut z = seed.wrapping_add((round as u64).wrapping_add(1).wrapping_mul(0x9E3779B97F4A7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
 
 pub struct ShuffledAndSorted {
     shuffled: Vec<usize>,
     sorted: Vec<usize>,
-    rng: StdRng
+    rng: StdRng,
+    mode: RoundMode,
 }
 
 impl ShuffledAndSorted {
-    pub fn new(length: usize, seed: u64) -> Self {
+    pub fn new(length: usize, seed: u64, mode: RoundMode) -> Self {
         let vec: Vec<usize> = (0..length).collect();
         Self {
             shuffled: vec.clone(),
             sorted: vec,
             rng: StdRng::seed_from_u64(seed),
+            mode,
         }
     }
 
-    pub fn shuffle(
-        &mut self,
-        max_distance: f64,
-        probabilty: f64,
-    ) -> Result<(), String> {
-        if probabilty < 0.0 || probabilty > 1.0 {
-            return Err("Probability must be between 0.0 and 1.0".to_string());
+    fn unshuffle(&mut self) {
+        for (i, value) in self.shuffled.iter_mut().enumerate() {
+            *value = i;
         }
-        
-        for i in 0..self.shuffled.len() {
-            if !self.rng.gen_bool(probabilty) {
-                continue;
-            }
+    }
 
-            let low = max(
-                0,
-                i as isize - max_distance as isize
-            ) as usize;
-            let high = min(
-                self.shuffled.len() as isize - 1,
-                i as isize + max_distance as isize
-            ) as usize;
+    //Draws the round from a generator seeded with round_seed, so the input of
+    //an independent round can be generated again from that seed alone.
+    pub fn shuffle_round(
+        &mut self,
+        distribution: &Distribution,
+        seed: u64,
+        round: usize,
+    ) -> Result<(), String> {
+        self.rng = StdRng::seed_from_u64(round_seed(seed, round));
+        self.shuffle(distribution)
+    }
 
-            let j = self.rng.gen_range(low..=high);
-            self.shuffled.swap(i, j);
+    pub fn shuffle(&mut self, distribution: &Distribution) -> Result<(), String> {
+        if self.mode == RoundMode::Independent {
+            self.unshuffle();
         }
 
-        Ok(())
+        distribution.shuffle(&mut self.shuffled, &mut self.rng)
+    }
+
+    pub fn shuffled(&self) -> &[usize] {
+        &self.shuffled
     }
 
     fn clone_shuffled(&mut self) {
@@ -66,114 +143,444 @@ impl ShuffledAndSorted {
         }
     }
 
-    pub fn quicksort(&mut self) -> QuicksortResult {
+    pub fn sort(&mut self, sorter: &dyn Sorter) -> Vec<u64> {
         self.clone_shuffled();
-        quicksort(&mut self.sorted)
+        sorter.sort(&mut self.sorted)
     }
 
-    pub fn shellsort(&mut self, gaps: &Vec<usize>) -> ShellsortResult {
+    pub fn shellsort_shared<G: AsRef<[usize]>>(
+        &mut self,
+        candidates: &[G],
+    ) -> Vec<ShellsortResult> {
         self.clone_shuffled();
-        shellsort(&mut self.sorted, gaps)
+        shellsort_shared(&mut self.sorted, candidates)
     }
 }
 
-pub enum SortResults {
-    ShellsortOnly(Vec<ShellsortResult>),
-    ShellAndQuicksort {
-        shellsort: Vec<ShellsortResult>,
-        quicksort: Vec<QuicksortResult>,
-    },
+pub fn perform_rounds(
+    experiment: &Experiment,
+    sorters: &[&dyn Sorter],
+    keep_values: bool,
+) -> Result<Vec<SorterResults>, String> {
+    let mut a: ShuffledAndSorted = ShuffledAndSorted::new(
+        experiment.length,
+        experiment.seed,
+        experiment.mode,
+    );
+
+    let mut results: Vec<SorterResults> = sorters
+        .iter()
+        .map(|&sorter| SorterResults::new(sorter, keep_values))
+        .collect();
+
+    for round in 0..experiment.rounds {
+        a.shuffle_round(&experiment.distribution, experiment.seed, round)?;
+
+        for (&sorter, results) in sorters.iter().zip(results.iter_mut()) {
+            results.add(&a.sort(sorter));
+        }
+    }
+
+    Ok(results)
 }
 
-pub fn perform_rounds(
-    length: usize,
-    seed: u64,
-    rounds: usize,
-    gaps: &Vec<usize>,
-    quicksort: bool,
-    max_distance: f64,
-    probability: f64,
-) -> Result<SortResults, String> {
-    let mut a: ShuffledAndSorted = ShuffledAndSorted::new(length, seed);
-
-    let mut results: SortResults = if quicksort {
-        SortResults::ShellAndQuicksort {
-            shellsort: Vec::with_capacity(rounds),
-            quicksort: Vec::with_capacity(rounds),
-        }
-    } else {
-        SortResults::ShellsortOnly(Vec::with_capacity(rounds))
+//Seeds every round like perform_rounds, so the results are the same for any
+//number of threads. Rounds must be independent.
+pub fn perform_rounds_parallel(
+    experiment: &Experiment,
+    sorters: &[&dyn Sorter],
+    keep_values: bool,
+    threads: usize,
+) -> Result<Vec<SorterResults>, String> {
+    if experiment.mode == RoundMode::Cumulative {
+        return Err("Cumulative rounds cannot be split between threads.".to_string());
+    }
+
+    let threads = threads.clamp(1, experiment.rounds.max(1));
+    let chunk = experiment.rounds.div_ceil(threads);
+
+    let new_results = || -> Vec<SorterResults> {
+        sorters
+            .iter()
+            .map(|&sorter| SorterResults::new(sorter, keep_values))
+            .collect()
     };
 
-    for _ in 0..rounds {
-        a.shuffle(max_distance, probability)?;
+    let chunks: Vec<Result<Vec<SorterResults>, String>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                scope.spawn(move || {
+                    let start = (t * chunk).min(experiment.rounds);
+                    let end = (start + chunk).min(experiment.rounds);
+                    let mut a = ShuffledAndSorted::new(
+                        experiment.length,
+                        experiment.seed,
+                        experiment.mode,
+                    );
+                    let mut results = new_results();
+
+                    for round in start..end {
+                        a.shuffle_round(&experiment.distribution, experiment.seed, round)?;
+
+                        for (&sorter, results) in sorters.iter().zip(results.iter_mut()) {
+                            results.add(&a.sort(sorter));
+                        }
+                    }
+
+                    Ok(results)
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("Sorting thread panicked."))
+            .collect()
+    });
+
+    //Merging in the order of the rounds keeps any values in that order.
+    let mut results = new_results();
+    for chunk in chunks {
+        for (results, chunk) in results.iter_mut().zip(chunk?) {
+            results.merge(&chunk);
+        }
+    }
+
+    Ok(results)
+}
+
+//Rounds each thread performs at a time when paired rounds are split between
+//threads. Their results wait to be handed on in the order of the rounds.
+const PARALLEL_BLOCK: usize = 16;
+
+//Every candidate sorts the same shuffled array in each round, so differences
+//between candidates are not blurred by differences between inputs.
+pub struct PairedRounds {
+    a: ShuffledAndSorted,
+    distribution: Distribution,
+    seed: u64,
+    mode: RoundMode,
+    threads: usize,
+    completed: usize,
+}
+
+impl PairedRounds {
+    //With threads, the rounds are seeded like on one thread and handed on in
+    //the same order, so the results are the same for any number of threads.
+    pub fn new(experiment: &Experiment, threads: usize) -> Self {
+        Self {
+            a: ShuffledAndSorted::new(experiment.length, experiment.seed, experiment.mode),
+            distribution: experiment.distribution.clone(),
+            seed: experiment.seed,
+            mode: experiment.mode,
+            threads,
+            completed: 0,
+        }
+    }
+
+    pub fn completed(&self) -> usize {
+        self.completed
+    }
+
+    //Moves past rounds that were already performed, without sorting, so a
+    //resumed run sees the same arrays. Only cumulative rounds need the
+    //earlier arrays to be shuffled again.
+    pub fn skip(&mut self, rounds: usize) -> Result<(), String> {
+        if self.mode == RoundMode::Cumulative {
+            for round in self.completed..self.completed + rounds {
+                self.a.shuffle_round(&self.distribution, self.seed, round)?;
+            }
+        }
+
+        self.completed += rounds;
+        Ok(())
+    }
+
+    //Hands the results of the candidates to add after every round, so the
+    //caller decides what to accumulate.
+    pub fn run<G: AsRef<[usize]> + Sync>(
+        &mut self,
+        rounds: usize,
+        candidates: &[G],
+        mut add: impl FnMut(&[ShellsortResult]),
+    ) -> Result<(), String> {
+        let end = self.completed + rounds;
+
+        if self.threads == 0 {
+            for round in self.completed..end {
+                self.a.shuffle_round(&self.distribution, self.seed, round)?;
+
+                add(&self.a.shellsort_shared(candidates));
+            }
+        } else {
+            if self.mode == RoundMode::Cumulative {
+                return Err("Cumulative rounds cannot be split between threads.".to_string());
+            }
+
+            let mut start = self.completed;
+            while start < end {
+                let stop = (start + self.threads * PARALLEL_BLOCK).min(end);
+
+                for round in self.run_parallel(start, stop, candidates)? {
+                    add(&round);
+                }
 
-        match results {
-            SortResults::ShellsortOnly(ref mut shellsort_results) => {
-                shellsort_results.push(a.shellsort(gaps));
-            },
-            SortResults::ShellAndQuicksort {
-                ref mut shellsort,
-                ref mut quicksort,
-            } => {
-                shellsort.push(a.shellsort(gaps));
-                quicksort.push(a.quicksort());
-            },
+                start = stop;
+            }
         }
+
+        self.completed = end;
+        Ok(())
     }
 
+    //The results of the rounds from start to end, in order.
+    fn run_parallel<G: AsRef<[usize]> + Sync>(
+        &self,
+        start: usize,
+        end: usize,
+        candidates: &[G],
+    ) -> Result<Vec<Vec<ShellsortResult>>, String> {
+        let threads = self.threads.clamp(1, (end - start).max(1));
+        let chunk = (end - start).div_ceil(threads);
+        let length = self.a.shuffled().len();
+
+        let chunks: Vec<Result<Vec<Vec<ShellsortResult>>, String>> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|t| {
+                    scope.spawn(move || {
+                        let first = (start + t * chunk).min(end);
+                        let last = (first + chunk).min(end);
+                        let mut a = ShuffledAndSorted::new(length, self.seed, self.mode);
+                        let mut rounds = Vec::with_capacity(last - first);
+
+                        for round in first..last {
+                            a.shuffle_round(&self.distribution, self.seed, round)?;
+                            rounds.push(a.shellsort_shared(candidates));
+                        }
+
+                        Ok(rounds)
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Sorting thread panicked."))
+                .collect()
+        });
+
+        let mut rounds = Vec::with_capacity(end - start);
+        for chunk in chunks {
+            rounds.extend(chunk?);
+        }
+
+        Ok(rounds)
+    }
+}
+
+pub fn perform_paired_rounds<G: AsRef<[usize]> + Sync>(
+    experiment: &Experiment,
+    candidates: &[G],
+    keep_values: bool,
+) -> Result<PairedResults, String> {
+    let mut results = PairedResults::new(candidates.len(), keep_values);
+
+    PairedRounds::new(experiment, 0)
+        .run(experiment.rounds, candidates, |round| results.add(round))?;
+
     Ok(results)
 }
 
+#[derive(Debug)]
 pub enum ReportError {
     EmptyResults,
+    InvalidConfidence(f64),
 }
 
-pub enum SortReport {
-    ShellsortOnly(ShellsortReport),
-    ShellAndQuicksort{
-        shellsort: ShellsortReport,
-        quicksort: QuicksortReport,
-    },
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReportError::EmptyResults => write!(f, "No results to report."),
+            ReportError::InvalidConfidence(confidence) => write!(
+                f,
+                "Confidence must be between 0 and 1, not {}.",
+                confidence,
+            ),
+        }
+    }
 }
 
-impl TryFrom<SortResults> for SortReport {
-    type Error = ReportError;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn experiment(rounds: usize) -> Experiment {
+        Experiment {
+            length: 100,
+            seed: 7,
+            rounds,
+            distribution: Distribution::Uniform,
+            mode: RoundMode::Independent,
+        }
+    }
+
+    #[test]
+    fn test_paired_rounds_share_inputs() {
+        let candidates = vec![vec![1, 4, 13], vec![1, 4, 10, 23]];
+        let paired = perform_paired_rounds(&experiment(5), &candidates, true).unwrap();
+
+        for (gaps, paired) in candidates.iter().zip(paired.results.iter()) {
+            let sorter = ShellsortSorter { gaps: gaps.clone() };
+            let single = perform_rounds(&experiment(5), &[&sorter], true).unwrap();
+
+            assert_eq!(paired.accumulators, single[0].accumulators, "same rounds");
+        }
+
+        let baseline = paired.results[0].accumulators[0].values().unwrap();
+        let candidate = paired.results[1].accumulators[0].values().unwrap();
+        let difference = paired.differences[1].comparisons.difference().unwrap();
+        let expected = Difference::new(
+            baseline.iter().copied(),
+            candidate.iter().copied(),
+        ).unwrap();
+
+        assert_eq!(difference.average, expected.average, "differences from the first");
+        assert_eq!(difference.wilcoxon_p_value, expected.wilcoxon_p_value);
+        assert_eq!(paired.differences[0].comparisons.difference().unwrap().ties, 5, "itself");
+    }
+
+    #[test]
+    fn test_parallel_rounds_ignore_thread_count() {
+        let shellsort = ShellsortSorter { gaps: vec![1, 4, 13, 40] };
+        let sorters: [&dyn Sorter; 2] = [&shellsort, &QuicksortSorter];
+
+        let expected = perform_rounds(&experiment(13), &sorters, true).unwrap();
 
-    fn try_from(results: SortResults) -> Result<Self, ReportError> {
-        match results {
-            SortResults::ShellsortOnly(shellsort_results) => {
-                let shellsort_report = ShellsortReport::try_from(shellsort_results)?;
-                Ok(SortReport::ShellsortOnly(shellsort_report))
+        for threads in [2, 4, 13, 32] {
+            let results = perform_rounds_parallel(
+                &experiment(13), &sorters, true, threads,
+            ).unwrap();
+
+            assert_eq!(results[0].rounds(), 13, "every round with {} threads", threads);
+
+            for (results, expected) in results.iter().zip(expected.iter()) {
+                for (a, b) in results.accumulators.iter().zip(expected.accumulators.iter()) {
+                    assert_eq!(a.values(), b.values(), "{} threads", threads);
+                    assert_eq!(a.sum(), b.sum(), "{} threads", threads);
+                }
             }
-            SortResults::ShellAndQuicksort { shellsort, quicksort } => {
-                let shellsort_report = ShellsortReport::try_from(shellsort)?;
-                let quicksort_report = QuicksortReport::try_from(quicksort)?;
-                Ok(SortReport::ShellAndQuicksort {
-                    shellsort: shellsort_report,
-                    quicksort: quicksort_report,
-                })
+        }
+    }
+
+    #[test]
+    fn test_paired_rounds_ignore_thread_count() {
+        let candidates = vec![vec![1, 4, 13], vec![1, 4, 10, 23]];
+        let expected = perform_paired_rounds(&experiment(37), &candidates, true).unwrap();
+
+        for threads in [1, 3, 40] {
+            let mut results = PairedResults::new(candidates.len(), true);
+            let mut rounds = PairedRounds::new(&experiment(37), threads);
+
+            //In two runs, as a resumed comparison would.
+            rounds.run(20, &candidates, |round| results.add(round)).unwrap();
+            rounds.run(17, &candidates, |round| results.add(round)).unwrap();
+
+            assert_eq!(results.rounds(), 37, "every round with {} threads", threads);
+            for (results, expected) in results.results.iter().zip(expected.results.iter()) {
+                assert_eq!(results.accumulators, expected.accumulators, "{} threads", threads);
             }
+            assert_eq!(results.differences, expected.differences, "{} threads", threads);
         }
+
+        let cumulative = Experiment { mode: RoundMode::Cumulative, ..experiment(4) };
+        assert!(PairedRounds::new(&cumulative, 2).run(4, &candidates, |_| ()).is_err());
     }
-}
 
-impl SortReport {
-    pub fn print(&self) {
-        match self {
-            SortReport::ShellsortOnly(ref report) => {
-                report.print();
-                println!();
-            },
-            SortReport::ShellAndQuicksort {
-                ref shellsort,
-                ref quicksort,
-            } => {
-                shellsort.print();
-                println!();
-                quicksort.print();
-                println!()
+    #[test]
+    fn test_round_seed_reproduces_input() {
+        let gaps = vec![1, 4, 13, 40];
+        let results = perform_rounds(
+            &experiment(6), &[&ShellsortSorter { gaps: gaps.clone() }], true,
+        ).unwrap();
+        let comparisons = results[0].accumulators[0].values().unwrap();
+
+        for (round, &comparisons) in comparisons.iter().enumerate() {
+            let mut a: Vec<usize> = (0..100).collect();
+            let mut rng = StdRng::seed_from_u64(round_seed(7, round));
+            Distribution::Uniform.shuffle(&mut a, &mut rng).unwrap();
+
+            assert_eq!(shellsort(&mut a, &gaps).comparisons, comparisons, "round {}", round);
+        }
+    }
+
+    #[test]
+    fn test_sampled_rounds_match_exhaustive() {
+        let gaps = vec![1, 3];
+        let sampled = Experiment {
+            length: 7,
+            rounds: 20000,
+            ..experiment(0)
+        };
+
+        let exact = perform_exhaustive(7, &[&gaps], false).unwrap();
+        let exact = &exact.shellsort[0];
+
+        let results = perform_rounds(&sampled, &[&ShellsortSorter { gaps }], false).unwrap();
+        let report = SorterReport::try_from(&results[0]).unwrap();
+        let comparisons = &report.metrics[0];
+
+        let standard_error = comparisons.stddev / (sampled.rounds as f64).sqrt();
+        let difference = comparisons.average - exact.comparisons.average();
+        assert!(
+            difference.abs() < 4.0 * standard_error,
+            "sampled {} is far from exact {}",
+            comparisons.average,
+            exact.comparisons.average(),
+        );
+        assert!(comparisons.most <= exact.comparisons.most(), "exact maximum bounds samples");
+    }
+
+    #[test]
+    fn test_sorter_per_report() {
+        let shellsort = ShellsortSorter { gaps: vec![1, 4, 13] };
+        let results = perform_rounds(
+            &experiment(4), &[&shellsort, &QuicksortSorter], false,
+        ).unwrap();
+
+        let names: Vec<String> = results
+            .iter()
+            .map(|results| SorterReport::try_from(results).unwrap().name)
+            .collect();
+        assert_eq!(names, vec!["Shellsort", "Quicksort"], "a report per sorter");
+        assert_eq!(results[1].metrics, &["comparisons", "swaps", "max depth"]);
+    }
+
+    #[test]
+    fn test_independent_rounds_restart_from_sorted() {
+        let mut cumulative = ShuffledAndSorted::new(1000, 3, RoundMode::Cumulative);
+        let mut independent = ShuffledAndSorted::new(1000, 3, RoundMode::Independent);
+
+        let displacement = |a: &ShuffledAndSorted| -> usize {
+            a.shuffled.iter().enumerate().map(|(i, v)| i.abs_diff(*v)).sum()
+        };
+
+        let mut first = 0;
+        let distribution = Distribution::BoundedSwaps {
+            max_distance: 2.0,
+            probability: 0.1,
+        };
+
+        for round in 0..50 {
+            cumulative.shuffle(&distribution).unwrap();
+            independent.shuffle(&distribution).unwrap();
+
+            if round == 0 {
+                first = displacement(&independent);
             }
+
+            assert!(displacement(&independent) < 2 * first, "no drift between rounds");
         }
+
+        assert!(displacement(&cumulative) > 4 * first, "cumulative rounds drift");
     }
-}
\ No newline at end of file
+}
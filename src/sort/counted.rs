@@ -0,0 +1,145 @@
+use std::{cell::Cell, cmp::Ordering};
+
+//Wraps a value so that every comparison between wrapped values adds one to
+//a shared counter. Any sort, including those in std, can then be measured
+//the same way as the instrumented sorts here.
+pub struct Counted<'a, T> {
+    pub value: T,
+    counter: &'a Cell<u64>,
+}
+
+impl<'a, T> Counted<'a, T> {
+    pub fn new(value: T, counter: &'a Cell<u64>) -> Self {
+        Self { value, counter }
+    }
+
+    pub fn wrap(values: &[T], counter: &'a Cell<u64>) -> Vec<Self>
+    where
+        T: Clone,
+    {
+        values
+            .iter()
+            .map(|value| Self::new(value.clone(), counter))
+            .collect()
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    fn count(&self) {
+        self.counter.set(self.counter.get() + 1);
+    }
+}
+
+impl<T: Clone> Clone for Counted<'_, T> {
+    fn clone(&self) -> Self {
+        Self::new(self.value.clone(), self.counter)
+    }
+}
+
+impl<T: Copy> Copy for Counted<'_, T> {}
+
+impl<T: PartialEq> PartialEq for Counted<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.count();
+        self.value == other.value
+    }
+}
+
+impl<T: Eq> Eq for Counted<'_, T> {}
+
+//The operators all go through partial_cmp, so each of them is one comparison.
+impl<T: PartialOrd> PartialOrd for Counted<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.count();
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl<T: Ord> Ord for Counted<'_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.count();
+        self.value.cmp(&other.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gap_sequences::GapSequence,
+        sort::{quicksort, shellsort, shellsort_by, Distribution},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn inputs() -> Vec<Vec<usize>> {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut inputs = vec![vec![], vec![1], (0..50).rev().collect()];
+
+        for distribution in ["uniform", "few_distinct:3", "organ_pipe"] {
+            let distribution: Distribution = distribution.parse().unwrap();
+            let mut a: Vec<usize> = (0..200).collect();
+            distribution.shuffle(&mut a, &mut rng).unwrap();
+            inputs.push(a);
+        }
+
+        inputs
+    }
+
+    #[test]
+    fn test_shellsort_counts_every_comparison() {
+        for input in inputs() {
+            let counter = Cell::new(0);
+            let mut a = Counted::wrap(&input, &counter);
+            let result = shellsort(&mut a, &[1, 4, 10, 23, 57]);
+
+            assert_eq!(result.comparisons, counter.get(), "length {}", input.len());
+        }
+    }
+
+    #[test]
+    fn test_quicksort_counts_every_comparison() {
+        for input in inputs() {
+            let counter = Cell::new(0);
+            let mut a = Counted::wrap(&input, &counter);
+            let result = quicksort(&mut a);
+
+            assert_eq!(result.comparisons, counter.get(), "length {}", input.len());
+        }
+    }
+
+    #[test]
+    fn test_shellsort_by_counts_every_comparison() {
+        for input in inputs() {
+            //Strings are not Copy, which shellsort_by does not need.
+            let strings: Vec<String> = input.iter().map(|value| format!("{:04}", value)).collect();
+            let counter = Cell::new(0);
+            let mut a = Counted::wrap(&strings, &counter);
+            let result = shellsort_by(&mut a, &GapSequence::Ciura2001, |x, y| x.cmp(y));
+
+            assert_eq!(result.comparisons, counter.get(), "length {}", input.len());
+            assert!(a.windows(2).all(|pair| pair[0].value <= pair[1].value), "sorted");
+
+            let mut b = input.clone();
+            let gaps = GapSequence::Ciura2001.to_vec(input.len());
+            assert_eq!(
+                shellsort(&mut b, &gaps).comparisons,
+                counter.get(),
+                "shellsort counts the same, length {}",
+                input.len(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_std_sort() {
+        let counter = Cell::new(0);
+        let mut a = Counted::wrap(&[3, 1, 2], &counter);
+        a.sort();
+
+        let sorted: Vec<usize> = a.into_iter().map(Counted::into_inner).collect();
+        assert_eq!(sorted, vec![1, 2, 3]);
+        assert!(counter.get() >= 2, "at least 2 comparisons sort 3 values");
+    }
+}
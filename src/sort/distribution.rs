@@ -0,0 +1,521 @@
+use std::{
+    cmp::{max, min},
+    fmt,
+    str::FromStr,
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
+#[derive(Clone, PartialEq)]
+pub enum Distribution {
+    Uniform,
+    BoundedSwaps {
+        max_distance: f64,
+        probability: f64,
+    },
+    Reversed,
+    OrganPipe,
+    Sawtooth {
+        teeth: usize,
+    },
+    RandomTail {
+        fraction: f64,
+    },
+    KSorted {
+        k: usize,
+    },
+    FewDistinct {
+        values: usize,
+    },
+    Zipf {
+        exponent: f64,
+    },
+    Runs {
+        runs: usize,
+    },
+}
+
+//The largest distance of a bounded swap, either given or the array length.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MaxDistance {
+    Length,
+    Custom(f64),
+}
+
+impl FromStr for MaxDistance {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "" => Ok(MaxDistance::Length),
+            _ => {
+                let max_distance = s.parse::<f64>()
+                .map_err(|_| "Invalid max distance provided.")?;
+
+                Ok(MaxDistance::Custom(max_distance))
+            }
+        }
+    }
+}
+
+impl MaxDistance {
+    pub fn at(&self, length: usize) -> f64 {
+        match self {
+            MaxDistance::Length => length as f64,
+            MaxDistance::Custom(max_distance) => *max_distance,
+        }
+    }
+}
+
+impl Distribution {
+    pub fn describe(&self) -> String {
+        match self {
+            Distribution::Uniform => "uniformly random permutations".to_string(),
+            Distribution::BoundedSwaps { max_distance, probability } => format!(
+                "permutations shuffled with maximum swap distance of {} \
+                and {:.1}% probability of each swap",
+                max_distance,
+                probability * 100.0,
+            ),
+            Distribution::Reversed => "reverse sorted arrays".to_string(),
+            Distribution::OrganPipe => "organ pipe arrays".to_string(),
+            Distribution::Sawtooth { teeth } => format!(
+                "sawtooth arrays with {} teeth",
+                teeth,
+            ),
+            Distribution::RandomTail { fraction } => format!(
+                "sorted arrays with a random tail of {:.1}% of the elements",
+                fraction * 100.0,
+            ),
+            Distribution::KSorted { k } => format!(
+                "random arrays with every element less than {} from its place",
+                k,
+            ),
+            Distribution::FewDistinct { values } => format!(
+                "random arrays of {} distinct values",
+                values,
+            ),
+            Distribution::Zipf { exponent } => format!(
+                "random arrays of Zipf distributed keys with exponent {}",
+                exponent,
+            ),
+            Distribution::Runs { runs } => format!(
+                "random arrays made of {} sorted runs",
+                runs,
+            ),
+        }
+    }
+
+    //Fills the array with the next input. Only the shuffles start from the
+    //current contents; the other inputs overwrite the array.
+    pub fn shuffle(&self, a: &mut [usize], rng: &mut StdRng) -> Result<(), String> {
+        let length = a.len();
+
+        match *self {
+            Distribution::Uniform => {
+                //Fisher-Yates, every permutation is equally likely.
+                a.shuffle(rng);
+            },
+            Distribution::BoundedSwaps { max_distance, probability } => {
+                bounded_swaps(a, rng, max_distance, probability)?;
+            },
+            Distribution::Reversed => {
+                for (i, value) in a.iter_mut().enumerate() {
+                    *value = length - 1 - i;
+                }
+            },
+            Distribution::OrganPipe => {
+                //Even values ascend to the middle, odd values descend after it.
+                let middle = length.div_ceil(2);
+                for (i, value) in a.iter_mut().enumerate() {
+                    *value = if i < middle {
+                        2 * i
+                    } else {
+                        2 * (length - 1 - i) + 1
+                    };
+                }
+            },
+            Distribution::Sawtooth { teeth } => {
+                if teeth == 0 {
+                    return Err("Sawtooth needs at least 1 tooth.".to_string());
+                }
+
+                //Every tooth ascends. The values are handed out a column at a
+                //time across the teeth, skipping the short last tooth once it
+                //runs out, so they are exactly 0..length.
+                let tooth_length = max(1, length.div_ceil(teeth));
+                let mut next = 0;
+                for j in 0..tooth_length {
+                    for i in (j..length).step_by(tooth_length) {
+                        a[i] = next;
+                        next += 1;
+                    }
+                }
+            },
+            Distribution::RandomTail { fraction } => {
+                if !(0.0..=1.0).contains(&fraction) {
+                    return Err("Fraction must be between 0.0 and 1.0".to_string());
+                }
+
+                let tail = (length as f64 * fraction).round() as usize;
+
+                fill_sorted(a);
+                a.shuffle(rng);
+                a[..length - tail].sort_unstable();
+            },
+            Distribution::KSorted { k } => {
+                if k == 0 {
+                    return Err("K must be at least 1.".to_string());
+                }
+
+                //Ordering by index plus a jitter below k keeps every value
+                //less than k places from where it belongs.
+                let mut keys: Vec<(f64, usize)> = (0..length)
+                    .map(|i| (i as f64 + rng.gen::<f64>() * k as f64, i))
+                    .collect();
+                keys.sort_unstable_by(|x, y| x.0.total_cmp(&y.0));
+
+                for (value, (_, i)) in a.iter_mut().zip(keys) {
+                    *value = i;
+                }
+            },
+            Distribution::FewDistinct { values } => {
+                if values == 0 {
+                    return Err("Few distinct needs at least 1 value.".to_string());
+                }
+
+                for value in a.iter_mut() {
+                    *value = rng.gen_range(0..values);
+                }
+            },
+            Distribution::Zipf { exponent } => {
+                if exponent.is_nan() || exponent < 0.0 {
+                    return Err("Exponent must not be negative.".to_string());
+                }
+
+                //Key r - 1 is drawn with weight 1 / r^exponent.
+                let mut cumulative = Vec::with_capacity(length);
+                let mut total = 0.0;
+                for rank in 1..=length {
+                    total += (rank as f64).powf(-exponent);
+                    cumulative.push(total);
+                }
+
+                for value in a.iter_mut() {
+                    let target = rng.gen::<f64>() * total;
+                    *value = cumulative
+                        .partition_point(|&weight| weight <= target)
+                        .min(length - 1);
+                }
+            },
+            Distribution::Runs { runs } => {
+                if runs == 0 {
+                    return Err("Runs needs at least 1 run.".to_string());
+                }
+
+                let run_length = max(1, length.div_ceil(runs));
+
+                fill_sorted(a);
+                a.shuffle(rng);
+                for run in a.chunks_mut(run_length) {
+                    run.sort_unstable();
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+//Display gives the same form FromStr accepts, so a distribution can be
+//stored in a checkpoint.
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Distribution::Uniform => write!(f, "uniform"),
+            Distribution::BoundedSwaps { max_distance, probability } => {
+                write!(f, "bounded_swaps:{},{}", max_distance, probability)
+            },
+            Distribution::Reversed => write!(f, "reversed"),
+            Distribution::OrganPipe => write!(f, "organ_pipe"),
+            Distribution::Sawtooth { teeth } => write!(f, "sawtooth:{}", teeth),
+            Distribution::RandomTail { fraction } => write!(f, "random_tail:{}", fraction),
+            Distribution::KSorted { k } => write!(f, "k_sorted:{}", k),
+            Distribution::FewDistinct { values } => write!(f, "few_distinct:{}", values),
+            Distribution::Zipf { exponent } => write!(f, "zipf:{}", exponent),
+            Distribution::Runs { runs } => write!(f, "runs:{}", runs),
+        }
+    }
+}
+
+//Accepts a name optionally followed by ':' and comma-separated parameters,
+//such as 'k_sorted:10'. Omitted parameters take the defaults listed in
+//distributions.txt.
+impl FromStr for Distribution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let (name, parameters) = match s.split_once(':') {
+            Some((name, parameters)) => (name, parameters.split(',').collect()),
+            None => (s.as_str(), Vec::new()),
+        };
+
+        let distribution = match name {
+            "uniform" => Distribution::Uniform,
+            "bounded_swaps" => Distribution::BoundedSwaps {
+                max_distance: parameter(&parameters, 0, None)?,
+                probability: parameter(&parameters, 1, Some(1.0))?,
+            },
+            "reversed" => Distribution::Reversed,
+            "organ_pipe" => Distribution::OrganPipe,
+            "sawtooth" => Distribution::Sawtooth {
+                teeth: parameter(&parameters, 0, Some(8))?,
+            },
+            "random_tail" => Distribution::RandomTail {
+                fraction: parameter(&parameters, 0, Some(0.1))?,
+            },
+            "k_sorted" => Distribution::KSorted {
+                k: parameter(&parameters, 0, Some(10))?,
+            },
+            "few_distinct" => Distribution::FewDistinct {
+                values: parameter(&parameters, 0, Some(10))?,
+            },
+            "zipf" => Distribution::Zipf {
+                exponent: parameter(&parameters, 0, Some(1.0))?,
+            },
+            "runs" => Distribution::Runs {
+                runs: parameter(&parameters, 0, Some(8))?,
+            },
+            _ => return Err(format!("Unknown input '{}'.", name)),
+        };
+
+        let expected = match distribution {
+            Distribution::BoundedSwaps { .. } => 2,
+            Distribution::Uniform | Distribution::Reversed | Distribution::OrganPipe => 0,
+            _ => 1,
+        };
+
+        if parameters.len() > expected {
+            return Err(format!("Too many parameters for input '{}'.", name));
+        }
+
+        Ok(distribution)
+    }
+}
+
+fn parameter<T: FromStr>(
+    parameters: &[&str],
+    index: usize,
+    default: Option<T>,
+) -> Result<T, String> {
+    match parameters.get(index) {
+        Some(parameter) => parameter.trim()
+            .parse::<T>()
+            .map_err(|_| format!("Invalid input parameter '{}'.", parameter)),
+        None => default.ok_or("Missing input parameter.".to_string()),
+    }
+}
+
+fn fill_sorted(a: &mut [usize]) {
+    for (i, value) in a.iter_mut().enumerate() {
+        *value = i;
+    }
+}
+
+//Swaps every element, with the given probability, with one at most
+//max_distance away. Even with max_distance at the length of the array this
+//does not produce every permutation with equal probability.
+fn bounded_swaps(
+    a: &mut [usize],
+    rng: &mut StdRng,
+    max_distance: f64,
+    probabilty: f64,
+) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&probabilty) {
+        return Err("Probability must be between 0.0 and 1.0".to_string());
+    }
+    
+    for i in 0..a.len() {
+        if !rng.gen_bool(probabilty) {
+            continue;
+        }
+
+        let low = max(
+            0,
+            i as isize - max_distance as isize
+        ) as usize;
+        let high = min(
+            a.len() as isize - 1,
+            i as isize + max_distance as isize
+        ) as usize;
+
+        let j = rng.gen_range(low..=high);
+        a.swap(i, j);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_uniform_is_uniform() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut counts = [0usize; 6];
+
+        //Count each of the 6 permutations of 3 elements.
+        for _ in 0..60000 {
+            let mut a = [0, 1, 2];
+            Distribution::Uniform.shuffle(&mut a, &mut rng).unwrap();
+
+            let index = match a {
+                [0, 1, 2] => 0,
+                [0, 2, 1] => 1,
+                [1, 0, 2] => 2,
+                [1, 2, 0] => 3,
+                [2, 0, 1] => 4,
+                _ => 5,
+            };
+            counts[index] += 1;
+        }
+
+        for count in counts {
+            assert!((9500..=10500).contains(&count), "{} is far from 10000", count);
+        }
+    }
+
+    #[test]
+    fn test_bounded_swaps_are_not_uniform() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let distribution = Distribution::BoundedSwaps {
+            max_distance: 3.0,
+            probability: 1.0,
+        };
+        let mut identity = 0;
+
+        for _ in 0..60000 {
+            let mut a = [0, 1, 2];
+            distribution.shuffle(&mut a, &mut rng).unwrap();
+
+            if a == [0, 1, 2] {
+                identity += 1;
+            }
+        }
+
+        //Each of the 27 equally likely swap choices is counted, and 4 of
+        //them leave the array sorted.
+        assert!((8400..=9400).contains(&identity), "{} is far from 8889", identity);
+    }
+
+    #[test]
+    fn test_bounded_swaps_probability() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let distribution = Distribution::BoundedSwaps {
+            max_distance: 3.0,
+            probability: 1.5,
+        };
+
+        assert!(distribution.shuffle(&mut [0, 1, 2], &mut rng).is_err());
+    }
+
+    fn generate(distribution: &str, length: usize) -> Vec<usize> {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut a: Vec<usize> = (0..length).collect();
+
+        Distribution::from_str(distribution)
+            .unwrap()
+            .shuffle(&mut a, &mut rng)
+            .unwrap();
+
+        a
+    }
+
+    fn is_permutation(a: &[usize]) -> bool {
+        let mut sorted = a.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+
+        sorted.len() == a.len()
+    }
+
+    fn descents(a: &[usize]) -> usize {
+        a.windows(2).filter(|pair| pair[0] > pair[1]).count()
+    }
+
+    #[test]
+    fn test_deterministic_inputs() {
+        assert_eq!(generate("reversed", 5), vec![4, 3, 2, 1, 0]);
+        assert_eq!(generate("organ_pipe", 5), vec![0, 2, 4, 3, 1]);
+        assert_eq!(generate("organ_pipe", 4), vec![0, 2, 3, 1]);
+        assert_eq!(generate("sawtooth:3", 7), vec![0, 3, 5, 1, 4, 6, 2]);
+    }
+
+    #[test]
+    fn test_sawtooth_is_permutation() {
+        for length in 0..40 {
+            for teeth in 1..8 {
+                let mut a = generate(&format!("sawtooth:{}", teeth), length);
+                assert!(descents(&a) < teeth, "{} teeth at length {}", teeth, length);
+
+                a.sort_unstable();
+                let expected: Vec<usize> = (0..length).collect();
+                assert_eq!(a, expected, "{} teeth at length {}", teeth, length);
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_inputs() {
+        let a = generate("random_tail:0.25", 100);
+        assert!(is_permutation(&a), "random tail is a permutation");
+        assert!(a[..75].windows(2).all(|pair| pair[0] < pair[1]), "head is sorted");
+
+        let a = generate("k_sorted:4", 1000);
+        assert!(is_permutation(&a), "k sorted is a permutation");
+        for (i, &value) in a.iter().enumerate() {
+            assert!(i.abs_diff(value) < 4, "{} is {} places away", value, i.abs_diff(value));
+        }
+
+        let a = generate("runs:5", 1000);
+        assert!(is_permutation(&a), "runs are a permutation");
+        assert!(descents(&a) <= 4, "5 runs have at most 4 descents");
+
+        let a = generate("few_distinct:3", 1000);
+        assert!(a.iter().all(|&value| value < 3), "only 3 values");
+
+        let a = generate("zipf:1.0", 1000);
+        let zeros = a.iter().filter(|&&value| value == 0).count();
+        let ones = a.iter().filter(|&&value| value == 1).count();
+        assert!(a.iter().all(|&value| value < 1000), "keys are within the length");
+        assert!(zeros > ones, "the first key is the most common");
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        for input in [
+            "uniform",
+            "bounded_swaps:5,0.5",
+            "reversed",
+            "organ_pipe",
+            "sawtooth:4",
+            "random_tail:0.05",
+            "k_sorted:7",
+            "few_distinct:2",
+            "zipf:1.5",
+            "runs:3",
+        ] {
+            let distribution = Distribution::from_str(input).unwrap();
+            assert_eq!(distribution.to_string(), input, "display matches parse");
+        }
+
+        assert!(Distribution::from_str("k_sorted").unwrap() == Distribution::KSorted { k: 10 });
+        assert!(Distribution::from_str("bounded_swaps").is_err(), "distance is needed");
+        assert!(Distribution::from_str("k_sorted:x").is_err());
+        assert!(Distribution::from_str("reversed:3").is_err());
+        assert!(Distribution::from_str("triangle").is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+
+use super::{
+    metrics::Normalized,
+    quicksort::quicksort as run_quicksort,
+    shared_passes::shellsort_shared,
+};
+
+//12! is already close to half a billion permutations.
+pub const MAX_EXHAUSTIVE_LENGTH: usize = 12;
+
+//Counts how many permutations produced each cost, so averages and maxima
+//are exact rather than estimated.
+#[derive(Clone, Default)]
+pub struct Histogram {
+    counts: BTreeMap<u64, u64>,
+}
+
+impl Histogram {
+    pub fn add(&mut self, value: u64) {
+        *self.counts.entry(value).or_insert(0) += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    pub fn total(&self) -> u128 {
+        self.counts
+            .iter()
+            .map(|(&value, &count)| value as u128 * count as u128)
+            .sum()
+    }
+
+    pub fn average(&self) -> f64 {
+        self.total() as f64 / self.count() as f64
+    }
+
+    pub fn fewest(&self) -> u64 {
+        self.counts.keys().next().copied().unwrap_or(0)
+    }
+
+    pub fn most(&self) -> u64 {
+        self.counts.keys().next_back().copied().unwrap_or(0)
+    }
+
+    pub fn counts(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.counts.iter().map(|(&value, &count)| (value, count))
+    }
+
+    pub fn print(&self, name: &str, length: usize) {
+        println!("Average {}: {:.4}", name, self.average());
+        Normalized::new(name, self.average(), length).print(name);
+        println!("Total {}: {}", name, self.total());
+        println!("Most {}: {}", name, self.most());
+        println!("Fewest {}: {}", name, self.fewest());
+        println!("Distribution of {} (value: permutations):", name);
+        for (value, count) in self.counts() {
+            println!("    {}: {}", value, count);
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct ExhaustiveShellsort {
+    pub comparisons: Histogram,
+    pub moves: Histogram,
+}
+
+impl ExhaustiveShellsort {
+    pub fn print(&self, length: usize) {
+        println!("Exact Shellsort Report:");
+        self.comparisons.print("comparisons", length);
+        self.moves.print("moves", length);
+    }
+}
+
+#[derive(Default)]
+pub struct ExhaustiveQuicksort {
+    pub comparisons: Histogram,
+    pub swaps: Histogram,
+    pub max_depth: Histogram,
+}
+
+impl ExhaustiveQuicksort {
+    pub fn print(&self, length: usize) {
+        println!("Exact Quicksort Report:");
+        self.comparisons.print("comparisons", length);
+        self.swaps.print("swaps", length);
+        self.max_depth.print("max depth", length);
+    }
+}
+
+pub struct ExhaustiveResults {
+    pub permutations: u64,
+    pub shellsort: Vec<ExhaustiveShellsort>,
+    pub quicksort: Option<ExhaustiveQuicksort>,
+}
+
+//Sorts every permutation of 0..length with each candidate, and with
+//quicksort if asked, instead of sampling random arrays.
+pub fn perform_exhaustive<G: AsRef<[usize]>>(
+    length: usize,
+    candidates: &[G],
+    quicksort: bool,
+) -> Result<ExhaustiveResults, String> {
+    if length == 0 || length > MAX_EXHAUSTIVE_LENGTH {
+        return Err(format!(
+            "Exhaustive runs need a length between 1 and {}.",
+            MAX_EXHAUSTIVE_LENGTH,
+        ));
+    }
+
+    let mut results = ExhaustiveResults {
+        permutations: 0,
+        shellsort: vec![ExhaustiveShellsort::default(); candidates.len()],
+        quicksort: if quicksort {
+            Some(ExhaustiveQuicksort::default())
+        } else {
+            None
+        },
+    };
+
+    let mut a = vec![0; length];
+
+    for_each_permutation(length, |permutation| {
+        results.permutations += 1;
+
+        a.copy_from_slice(permutation);
+        let shellsort_results = shellsort_shared(&mut a, candidates);
+
+        for (histograms, result) in results.shellsort.iter_mut().zip(shellsort_results) {
+            histograms.comparisons.add(result.comparisons);
+            histograms.moves.add(result.moves);
+        }
+
+        if let Some(ref mut histograms) = results.quicksort {
+            a.copy_from_slice(permutation);
+            let result = run_quicksort(&mut a);
+
+            histograms.comparisons.add(result.comparisons);
+            histograms.swaps.add(result.swaps);
+            histograms.max_depth.add(result.max_depth);
+        }
+    });
+
+    Ok(results)
+}
+
+//Heap's algorithm, which reaches each next permutation with a single swap.
+fn for_each_permutation<F: FnMut(&[usize])>(length: usize, mut f: F) {
+    let mut a: Vec<usize> = (0..length).collect();
+    let mut c = vec![0; length];
+
+    f(&a);
+
+    let mut i = 1;
+    while i < length {
+        if c[i] < i {
+            if i % 2 == 0 {
+                a.swap(0, i);
+            } else {
+                a.swap(c[i], i);
+            }
+
+            f(&a);
+
+            c[i] += 1;
+            i = 1;
+        } else {
+            c[i] = 0;
+            i += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_every_permutation_once() {
+        let mut seen: HashSet<Vec<usize>> = HashSet::new();
+        for_each_permutation(5, |permutation| {
+            assert!(seen.insert(permutation.to_vec()), "{:?} repeated", permutation);
+        });
+
+        assert_eq!(seen.len(), 120, "5! permutations");
+    }
+
+    #[test]
+    fn test_insertion_sort_is_exact() {
+        let results = perform_exhaustive(5, &[vec![1]], true).unwrap();
+        let insertion = &results.shellsort[0];
+
+        assert_eq!(results.permutations, 120);
+        assert_eq!(insertion.comparisons.most(), 10, "reversed compares every pair");
+        assert_eq!(insertion.comparisons.fewest(), 4, "sorted compares neighbours");
+        //Every element is moved once more than its inversions, and half of
+        //the 10 pairs are inverted on average.
+        assert_eq!(insertion.moves.average(), 9.0);
+        assert_eq!(results.quicksort.unwrap().comparisons.count(), 120);
+    }
+
+    #[test]
+    fn test_exhaustive_length_limit() {
+        assert!(perform_exhaustive(0, &[vec![1]], false).is_err());
+        assert!(perform_exhaustive(MAX_EXHAUSTIVE_LENGTH + 1, &[vec![1]], false).is_err());
+    }
+}
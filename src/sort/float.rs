@@ -0,0 +1,352 @@
+use std::{fmt, ops::Range, str::FromStr};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::gap_sequences::Gaps;
+
+use super::{
+    quicksort::{quicksort, QuicksortResult, QuicksortSorter},
+    shellsort::{shellsort, ShellsortResult, ShellsortSorter},
+    round_seed, Experiment, ShuffledAndSorted, SorterResults,
+};
+
+//NaN is not ordered against anything, so comparison sorts leave arrays
+//containing one unsorted. The float sorts move every NaN to one end first
+//and only sort the values in between.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NanPlacement {
+    First,
+    Last,
+}
+
+impl fmt::Display for NanPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NanPlacement::First => write!(f, "first"),
+            NanPlacement::Last => write!(f, "last"),
+        }
+    }
+}
+
+impl FromStr for NanPlacement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "" | "last" => Ok(NanPlacement::Last),
+            "first" => Ok(NanPlacement::First),
+            _ => Err("Invalid NaN placement provided.".to_string()),
+        }
+    }
+}
+
+//Moves every NaN to the end given by placement and returns the range of
+//the remaining values, which keep their order.
+pub fn partition_nans(a: &mut [f64], placement: NanPlacement) -> Range<usize> {
+    match placement {
+        NanPlacement::Last => {
+            let mut end = 0;
+            for i in 0..a.len() {
+                if !a[i].is_nan() {
+                    a.swap(end, i);
+                    end += 1;
+                }
+            }
+
+            0..end
+        },
+        NanPlacement::First => {
+            let mut start = a.len();
+            for i in (0..a.len()).rev() {
+                if !a[i].is_nan() {
+                    start -= 1;
+                    a.swap(start, i);
+                }
+            }
+
+            start..a.len()
+        },
+    }
+}
+
+//For total order instead, including the sign of zeros and NaNs, use
+//shellsort_by with f64::total_cmp.
+pub fn shellsort_f64<G: Gaps + ?Sized>(
+    a: &mut [f64],
+    gaps: &G,
+    placement: NanPlacement,
+) -> ShellsortResult {
+    let values = partition_nans(a, placement);
+    let gaps = gaps.gaps(values.len());
+
+    shellsort(&mut a[values], &gaps)
+}
+
+pub fn quicksort_f64(a: &mut [f64], placement: NanPlacement) -> QuicksortResult {
+    let values = partition_nans(a, placement);
+
+    quicksort(&mut a[values])
+}
+
+//Where the f64 values come from. Permutation keeps the inputs of the
+//distribution, so they match the integer rounds. The others draw new values
+//every round and ignore the distribution.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FloatValues {
+    Permutation,
+    //Uniformly random in [0, 1), so values are almost never equal.
+    Uniform,
+    //Half are -inf, -0.0, 0.0 or inf in equal parts, the rest uniformly
+    //random in [-1, 1). Zeros of either sign compare equal.
+    Specials,
+}
+
+impl fmt::Display for FloatValues {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FloatValues::Permutation => write!(f, "permutation"),
+            FloatValues::Uniform => write!(f, "uniform"),
+            FloatValues::Specials => write!(f, "specials"),
+        }
+    }
+}
+
+impl FromStr for FloatValues {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "" | "permutation" => Ok(FloatValues::Permutation),
+            "uniform" => Ok(FloatValues::Uniform),
+            "specials" => Ok(FloatValues::Specials),
+            _ => Err("Invalid float values provided.".to_string()),
+        }
+    }
+}
+
+impl FloatValues {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            FloatValues::Permutation => "permuted integers",
+            FloatValues::Uniform => "uniformly random f64 values in [0, 1)",
+            FloatValues::Specials => "f64 values with signed zeros and infinities",
+        }
+    }
+
+    fn value(&self, shuffled: usize, rng: &mut StdRng) -> f64 {
+        match self {
+            FloatValues::Permutation => shuffled as f64,
+            FloatValues::Uniform => rng.gen::<f64>(),
+            FloatValues::Specials => match rng.gen_range(0..8) {
+                0 => f64::NEG_INFINITY,
+                1 => -0.0,
+                2 => 0.0,
+                3 => f64::INFINITY,
+                _ => rng.gen_range(-1.0..1.0),
+            },
+        }
+    }
+}
+
+pub struct FloatKeys {
+    pub values: FloatValues,
+    pub nan_fraction: f64,
+    pub placement: NanPlacement,
+}
+
+//Sorts f64 values drawn as keys.values asks for, with each value replaced
+//by NaN with the given probability. Only the values are counted.
+pub fn perform_float_rounds(
+    experiment: &Experiment,
+    gaps: &[usize],
+    quicksort: bool,
+    keys: &FloatKeys,
+    keep_values: bool,
+) -> Result<Vec<SorterResults>, String> {
+    if !(0.0..=1.0).contains(&keys.nan_fraction) {
+        return Err("NaN fraction must be between 0.0 and 1.0".to_string());
+    }
+
+    let mut a: ShuffledAndSorted = ShuffledAndSorted::new(
+        experiment.length,
+        experiment.seed,
+        experiment.mode,
+    );
+
+    //NaNs are drawn after the shuffle, so the values match the integer rounds.
+    let mut shellsort_results = SorterResults::new(
+        &ShellsortSorter { gaps: gaps.to_vec() },
+        keep_values,
+    );
+    let mut quicksort_results = SorterResults::new(&QuicksortSorter, keep_values);
+    let mut values: Vec<f64> = vec![0.0; experiment.length];
+    let mut sorted: Vec<f64> = vec![0.0; experiment.length];
+
+    for round in 0..experiment.rounds {
+        if keys.values == FloatValues::Permutation {
+            a.shuffle_round(&experiment.distribution, experiment.seed, round)?;
+        } else {
+            a.rng = StdRng::seed_from_u64(round_seed(experiment.seed, round));
+        }
+
+        for (value, &shuffled) in values.iter_mut().zip(a.shuffled.iter()) {
+            *value = if a.rng.gen_bool(keys.nan_fraction) {
+                f64::NAN
+            } else {
+                keys.values.value(shuffled, &mut a.rng)
+            };
+        }
+
+        sorted.copy_from_slice(&values);
+        let result = shellsort_f64(&mut sorted, gaps, keys.placement);
+        shellsort_results.add(&[result.comparisons, result.moves]);
+
+        if quicksort {
+            sorted.copy_from_slice(&values);
+            let result = quicksort_f64(&mut sorted, keys.placement);
+            quicksort_results.add(&[result.comparisons, result.swaps, result.max_depth]);
+        }
+    }
+
+    let mut results = vec![shellsort_results];
+    if quicksort {
+        results.push(quicksort_results);
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::{perform_rounds, shellsort_by, Distribution, RoundMode};
+
+    fn is_sorted(a: &[f64]) -> bool {
+        a.windows(2).all(|pair| pair[0] <= pair[1])
+    }
+
+    #[test]
+    fn test_nan_placement() {
+        let a = [3.0, f64::NAN, -1.0, 2.5, f64::NAN, 0.0];
+
+        let mut last = a;
+        shellsort_f64(&mut last, &[1, 4], NanPlacement::Last);
+        assert!(is_sorted(&last[..4]), "{:?} is not sorted", last);
+        assert!(last[4..].iter().all(|x| x.is_nan()), "NaNs are last");
+
+        let mut first = a;
+        quicksort_f64(&mut first, NanPlacement::First);
+        assert!(first[..2].iter().all(|x| x.is_nan()), "NaNs are first");
+        assert!(is_sorted(&first[2..]), "{:?} is not sorted", first);
+
+        let mut nans = [f64::NAN; 3];
+        let result = quicksort_f64(&mut nans, NanPlacement::Last);
+        assert_eq!(result.comparisons, 0, "only NaNs");
+    }
+
+    #[test]
+    fn test_total_order() {
+        let mut a = [1.0, -f64::NAN, 0.0, f64::NAN, -0.0, -2.0];
+        shellsort_by(&mut a, &[1, 4], f64::total_cmp);
+
+        assert!(a[0].is_nan() && a[0].is_sign_negative(), "negative NaN first");
+        assert!(a[5].is_nan() && a[5].is_sign_positive(), "positive NaN last");
+        assert_eq!(a[1..5], [-2.0, -0.0, 0.0, 1.0]);
+        assert!(a[2].is_sign_negative(), "negative zero before zero");
+    }
+
+    #[test]
+    fn test_float_rounds() {
+        let experiment = Experiment {
+            length: 100,
+            seed: 3,
+            rounds: 5,
+            distribution: Distribution::Uniform,
+            mode: RoundMode::Independent,
+        };
+        let gaps = [1, 4, 13, 40];
+
+        let integers = perform_rounds(
+            &experiment, &[&ShellsortSorter { gaps: gaps.to_vec() }], true,
+        ).unwrap();
+        let integers = integers[0].accumulators[0].values().unwrap();
+
+        let keys = FloatKeys {
+            values: FloatValues::Permutation,
+            nan_fraction: 0.0,
+            placement: NanPlacement::Last,
+        };
+        let floats = perform_float_rounds(&experiment, &gaps, false, &keys, true).unwrap();
+
+        assert_eq!(floats.len(), 1, "quicksort results were not requested");
+        assert_eq!(
+            floats[0].accumulators[0].values().unwrap(),
+            integers,
+            "same arrays without NaN",
+        );
+
+        let keys = FloatKeys {
+            values: FloatValues::Permutation,
+            nan_fraction: 0.5,
+            placement: NanPlacement::First,
+        };
+        let floats = perform_float_rounds(&experiment, &gaps, true, &keys, true).unwrap();
+
+        assert_eq!(floats[1].name, "Quicksort");
+        assert_eq!(floats[1].rounds(), 5);
+        assert!(
+            floats[0].accumulators[0].values().unwrap()[0] < integers[0],
+            "NaNs are not sorted",
+        );
+    }
+
+    #[test]
+    fn test_float_values() {
+        let experiment = Experiment {
+            length: 200,
+            seed: 5,
+            rounds: 4,
+            distribution: Distribution::Uniform,
+            mode: RoundMode::Independent,
+        };
+        let gaps = [1, 4, 13, 40];
+
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..100 {
+            let value = FloatValues::Uniform.value(0, &mut rng);
+            assert!((0.0..1.0).contains(&value), "{} is not in [0, 1)", value);
+        }
+
+        let specials: Vec<f64> = (0..100)
+            .map(|_| FloatValues::Specials.value(0, &mut rng))
+            .collect();
+        assert!(specials.contains(&f64::INFINITY) && specials.contains(&f64::NEG_INFINITY));
+        assert!(specials.iter().any(|x| *x == 0.0 && x.is_sign_negative()), "negative zero");
+        assert!(specials.iter().any(|x| *x == 0.0 && x.is_sign_positive()), "positive zero");
+
+        let mut sorted = specials.clone();
+        shellsort_f64(&mut sorted, &gaps, NanPlacement::Last);
+        assert!(is_sorted(&sorted), "{:?} is not sorted", sorted);
+
+        for values in [FloatValues::Uniform, FloatValues::Specials] {
+            let keys = FloatKeys {
+                values,
+                nan_fraction: 0.1,
+                placement: NanPlacement::Last,
+            };
+            let results = perform_float_rounds(&experiment, &gaps, true, &keys, true).unwrap();
+            let again = perform_float_rounds(&experiment, &gaps, true, &keys, true).unwrap();
+
+            assert_eq!(results[0].rounds(), 4);
+            assert_eq!(
+                results[0].accumulators[0].values(),
+                again[0].accumulators[0].values(),
+                "the seed draws the same {} values",
+                values,
+            );
+        }
+
+        assert_eq!(FloatValues::from_str("Specials"), Ok(FloatValues::Specials));
+        assert!(FloatValues::from_str("normal").is_err());
+    }
+}
@@ -0,0 +1,488 @@
+use std::{fmt, str::FromStr};
+
+use super::{
+    statistics::{confidence_margin, log2_factorial},
+    ReportError,
+};
+
+//Updates the statistics of one counter a round at a time (Welford), so
+//memory does not grow with the number of rounds. The values themselves are
+//only kept when medians or percentiles are wanted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Accumulator {
+    count: u64,
+    sum: u128,
+    mean: f64,
+    m2: f64,
+    most: u64,
+    fewest: u64,
+    values: Option<Vec<u64>>,
+}
+
+impl Accumulator {
+    pub fn new(keep_values: bool) -> Self {
+        Self {
+            count: 0,
+            sum: 0,
+            mean: 0.0,
+            m2: 0.0,
+            most: 0,
+            fewest: u64::MAX,
+            values: if keep_values {
+                Some(Vec::new())
+            } else {
+                None
+            },
+        }
+    }
+
+    pub fn from_values(values: &[u64], keep_values: bool) -> Self {
+        let mut accumulator = Self::new(keep_values);
+        for &value in values {
+            accumulator.add(value);
+        }
+
+        accumulator
+    }
+
+    pub fn add(&mut self, value: u64) {
+        self.count += 1;
+        self.sum += value as u128;
+
+        let delta = value as f64 - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value as f64 - self.mean);
+
+        self.most = self.most.max(value);
+        self.fewest = self.fewest.min(value);
+
+        if let Some(ref mut values) = self.values {
+            values.push(value);
+        }
+    }
+
+    //Combines the statistics of rounds counted separately, such as on other
+    //threads. Values are only kept if both sides kept them.
+    pub fn merge(&mut self, other: &Accumulator) {
+        if other.count == 0 {
+            return;
+        }
+
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 += other.m2
+            + delta * delta * (self.count as f64 * other.count as f64 / count as f64);
+        self.count = count;
+        self.sum += other.sum;
+        self.most = self.most.max(other.most);
+        self.fewest = self.fewest.min(other.fewest);
+
+        match (&mut self.values, &other.values) {
+            (Some(values), Some(other_values)) => values.extend_from_slice(other_values),
+            _ => self.values = None,
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn sum(&self) -> u128 {
+        self.sum
+    }
+
+    //Taken from the exact sum rather than the running mean.
+    pub fn average(&self) -> f64 {
+        self.sum as f64 / self.count as f64
+    }
+
+    pub fn variance(&self) -> f64 {
+        self.m2 / self.count as f64
+    }
+
+    //Bessel's correction, for estimating the spread of all possible rounds
+    //rather than describing the rounds that were run.
+    pub fn sample_variance(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+
+        self.m2 / (self.count - 1) as f64
+    }
+
+    pub fn standard_error(&self) -> f64 {
+        (self.sample_variance() / self.count as f64).sqrt()
+    }
+
+    pub fn most(&self) -> u64 {
+        self.most
+    }
+
+    pub fn fewest(&self) -> u64 {
+        self.fewest
+    }
+
+    pub fn values(&self) -> Option<&[u64]> {
+        self.values.as_deref()
+    }
+}
+
+//Written to checkpoints as `count sum mean m2 most fewest`, followed by the
+//kept values as `[value,...]`. f64 values are printed so that they parse
+//back exactly.
+impl fmt::Display for Accumulator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {}",
+            self.count, self.sum, self.mean, self.m2, self.most, self.fewest,
+        )?;
+
+        if let Some(ref values) = self.values {
+            let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+            write!(f, " [{}]", values.join(","))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Accumulator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid accumulator '{}'.", s);
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 6 && fields.len() != 7 {
+            return Err(invalid());
+        }
+
+        let values = match fields.get(6) {
+            Some(field) => Some(
+                field
+                    .strip_prefix('[')
+                    .and_then(|field| field.strip_suffix(']'))
+                    .ok_or_else(invalid)?
+                    .split(',')
+                    .filter(|value| !value.is_empty())
+                    .map(|value| value.parse::<u64>())
+                    .collect::<Result<Vec<u64>, _>>()
+                    .map_err(|_| invalid())?
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            count: fields[0].parse().map_err(|_| invalid())?,
+            sum: fields[1].parse().map_err(|_| invalid())?,
+            mean: fields[2].parse().map_err(|_| invalid())?,
+            m2: fields[3].parse().map_err(|_| invalid())?,
+            most: fields[4].parse().map_err(|_| invalid())?,
+            fewest: fields[5].parse().map_err(|_| invalid())?,
+            values,
+        })
+    }
+}
+
+pub struct ReportSettings {
+    pub percentiles: Vec<f64>,
+    pub confidence: f64,
+    //The length of the sorted arrays, to also report normalized averages.
+    pub length: Option<usize>,
+}
+
+impl ReportSettings {
+    pub fn check(&self) -> Result<(), ReportError> {
+        if !(self.confidence > 0.0 && self.confidence < 1.0) {
+            return Err(ReportError::InvalidConfidence(self.confidence));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for ReportSettings {
+    fn default() -> Self {
+        Self {
+            percentiles: Vec::new(),
+            confidence: 0.95,
+            length: None,
+        }
+    }
+}
+
+//An average divided by what it is expected to grow with, so results at very
+//different lengths can be read on the same scale. Comparisons are measured
+//against the log2(n!) lower bound and n log2 n, moves and swaps against n.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Normalized {
+    pub per_log2_factorial: Option<f64>,
+    pub per_n_log2_n: Option<f64>,
+    pub per_n: Option<f64>,
+}
+
+impl Normalized {
+    pub fn new(metric: &str, average: f64, length: usize) -> Self {
+        let n = length as f64;
+        let per = |scale: f64| if scale > 0.0 { Some(average / scale) } else { None };
+
+        match metric {
+            "comparisons" => Self {
+                per_log2_factorial: per(log2_factorial(length)),
+                per_n_log2_n: per(n * n.log2()),
+                per_n: None,
+            },
+            "moves" | "swaps" => Self {
+                per_n: per(n),
+                ..Self::default()
+            },
+            _ => Self::default(),
+        }
+    }
+
+    pub fn print(&self, metric: &str) {
+        if let Some(value) = self.per_log2_factorial {
+            println!("Average {} per log2(n!): {:.4}", metric, value);
+        }
+        if let Some(value) = self.per_n_log2_n {
+            println!("Average {} per n log2 n: {:.4}", metric, value);
+        }
+        if let Some(value) = self.per_n {
+            println!("Average {} per n: {:.4}", metric, value);
+        }
+    }
+}
+
+//Summarizes one named counter over every round, the same way for every
+//algorithm and counter.
+pub struct MetricReport {
+    pub name: &'static str,
+    pub count: u64,
+    pub average: f64,
+    pub stddev: f64,
+    pub sample_stddev: f64,
+    pub standard_error: f64,
+    pub confidence: f64,
+    pub interval: Option<(f64, f64)>,
+    pub median: Option<f64>,
+    pub percentiles: Vec<(f64, f64)>,
+    pub most: u64,
+    pub fewest: u64,
+    pub normalized: Normalized,
+}
+
+impl MetricReport {
+    //Percentiles are given from 0 to 100, and like the median are only
+    //reported when the accumulator kept its values. The interval of the
+    //average needs at least two rounds.
+    pub fn new(
+        name: &'static str,
+        accumulator: &Accumulator,
+        settings: &ReportSettings,
+    ) -> Result<Self, ReportError> {
+        settings.check()?;
+
+        if accumulator.count() == 0 {
+            return Err(ReportError::EmptyResults);
+        }
+
+        let sorted = accumulator.values().map(|values| {
+            let mut sorted = values.to_vec();
+            sorted.sort_unstable();
+            sorted
+        });
+
+        let average = accumulator.average();
+        let standard_error = accumulator.standard_error();
+        let interval = confidence_margin(settings.confidence, standard_error, accumulator.count())
+            .map(|margin| (average - margin, average + margin));
+
+        Ok(Self {
+            name,
+            count: accumulator.count(),
+            average,
+            stddev: accumulator.variance().sqrt(),
+            sample_stddev: accumulator.sample_variance().sqrt(),
+            standard_error,
+            confidence: settings.confidence,
+            interval,
+            median: sorted.as_ref().map(|sorted| percentile(sorted, 50.0)),
+            percentiles: match sorted {
+                Some(ref sorted) => settings.percentiles
+                    .iter()
+                    .map(|&p| (p, percentile(sorted, p)))
+                    .collect(),
+                None => Vec::new(),
+            },
+            most: accumulator.most(),
+            fewest: accumulator.fewest(),
+            normalized: settings.length.map_or(Normalized::default(), |length| {
+                Normalized::new(name, average, length)
+            }),
+        })
+    }
+
+    pub fn print(&self) {
+        println!("Average {}: {:.2}", self.name, self.average);
+        println!("Std Dev {}: {:.2}", self.name, self.stddev);
+        println!("Sample Std Dev {}: {:.2}", self.name, self.sample_stddev);
+        println!("Std Error {}: {:.2}", self.name, self.standard_error);
+        if let Some((low, high)) = self.interval {
+            println!(
+                "{}% CI {}: {:.2} to {:.2}",
+                (self.confidence * 1000.0).round() / 10.0,
+                self.name,
+                low,
+                high,
+            );
+        }
+        if let Some(median) = self.median {
+            println!("Median {}: {:.2}", self.name, median);
+        }
+        for (p, value) in self.percentiles.iter() {
+            println!("P{} {}: {:.2}", p, self.name, value);
+        }
+        println!("Most {}: {}", self.name, self.most);
+        println!("Fewest {}: {}", self.name, self.fewest);
+        self.normalized.print(self.name);
+    }
+}
+
+//Interpolates linearly between the two closest ranks.
+pub fn percentile(sorted: &[u64], p: f64) -> f64 {
+    let rank = (p.clamp(0.0, 100.0) / 100.0) * (sorted.len() - 1) as f64;
+    let low = rank.floor() as usize;
+    let high = rank.ceil() as usize;
+    let fraction = rank - low as f64;
+
+    sorted[low] as f64 + (sorted[high] as f64 - sorted[low] as f64) * fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metric_report() {
+        let accumulator = Accumulator::from_values(&[4, 1, 3, 2, 10], true);
+        let settings = ReportSettings {
+            percentiles: vec![25.0, 90.0],
+            ..ReportSettings::default()
+        };
+        let report = MetricReport::new("comparisons", &accumulator, &settings).unwrap();
+
+        assert_eq!(report.count, 5);
+        assert_eq!(report.average, 4.0);
+        assert!((report.stddev - 10.0f64.sqrt()).abs() < 1e-9, "population stddev");
+        assert_eq!(report.median, Some(3.0));
+        assert_eq!(report.percentiles[0], (25.0, 2.0));
+        assert!((report.percentiles[1].1 - 7.6).abs() < 1e-9, "interpolated 90th percentile");
+        assert_eq!(report.most, 10);
+        assert_eq!(report.fewest, 1);
+
+        let streamed = MetricReport::new(
+            "comparisons",
+            &Accumulator::from_values(&[4, 1, 3, 2, 10], false),
+            &settings,
+        ).unwrap();
+        assert_eq!(streamed.average, report.average, "same average without values");
+        assert!(streamed.median.is_none() && streamed.percentiles.is_empty());
+    }
+
+    #[test]
+    fn test_normalized() {
+        let accumulator = Accumulator::from_values(&[6, 8], false);
+        let settings = ReportSettings {
+            length: Some(4),
+            ..ReportSettings::default()
+        };
+
+        //log2(4!) is 4.585 and 4 log2 4 is 8.
+        let comparisons = MetricReport::new("comparisons", &accumulator, &settings).unwrap();
+        assert!((comparisons.normalized.per_log2_factorial.unwrap() - 7.0 / 24f64.log2()).abs() < 1e-9);
+        assert_eq!(comparisons.normalized.per_n_log2_n, Some(7.0 / 8.0));
+        assert_eq!(comparisons.normalized.per_n, None);
+
+        let moves = MetricReport::new("moves", &accumulator, &settings).unwrap();
+        assert_eq!(moves.normalized.per_n, Some(7.0 / 4.0));
+
+        let depth = MetricReport::new("max depth", &accumulator, &settings).unwrap();
+        assert_eq!(depth.normalized, Normalized::default(), "nothing to scale depth by");
+
+        let unknown = MetricReport::new("moves", &accumulator, &ReportSettings::default()).unwrap();
+        assert_eq!(unknown.normalized, Normalized::default(), "length unknown");
+    }
+
+    #[test]
+    fn test_percentile_interpolates() {
+        assert_eq!(percentile(&[1, 2, 3, 4], 50.0), 2.5, "even count median");
+        assert_eq!(percentile(&[7], 99.0), 7.0, "single value");
+        assert_eq!(percentile(&[1, 5], 0.0), 1.0);
+        assert_eq!(percentile(&[1, 5], 100.0), 5.0);
+        let settings = ReportSettings::default();
+        assert!(MetricReport::new("moves", &Accumulator::new(true), &settings).is_err());
+    }
+
+    #[test]
+    fn test_confidence_interval() {
+        let accumulator = Accumulator::from_values(&[4, 1, 3, 2, 10], false);
+        let settings = ReportSettings::default();
+        let report = MetricReport::new("moves", &accumulator, &settings).unwrap();
+
+        //The sample variance of the values is 12.5, and t(0.975, 4) is 2.776.
+        assert!((report.sample_stddev - 12.5f64.sqrt()).abs() < 1e-9, "sample stddev");
+        assert!((report.standard_error - 2.5f64.sqrt()).abs() < 1e-9, "standard error");
+
+        let (low, high) = report.interval.unwrap();
+        assert!((high - 4.0 - 2.776 * 2.5f64.sqrt()).abs() < 1e-2, "{} to {}", low, high);
+        assert!((4.0 - low - (high - 4.0)).abs() < 1e-9, "interval is symmetric");
+
+        let single = Accumulator::from_values(&[4], false);
+        let report = MetricReport::new("moves", &single, &settings).unwrap();
+        assert!(report.interval.is_none(), "one round has no interval");
+
+        for confidence in [0.0, 1.0, 1.5, f64::NAN] {
+            let settings = ReportSettings { confidence, ..ReportSettings::default() };
+            assert!(
+                matches!(
+                    MetricReport::new("moves", &accumulator, &settings),
+                    Err(ReportError::InvalidConfidence(_)),
+                ),
+                "confidence {}",
+                confidence,
+            );
+        }
+    }
+
+    #[test]
+    fn test_accumulator_merge() {
+        let values: Vec<u64> = (0..100).map(|i| (i * 7919) % 113).collect();
+        let whole = Accumulator::from_values(&values, true);
+
+        let mut merged = Accumulator::new(true);
+        for chunk in values.chunks(17) {
+            merged.merge(&Accumulator::from_values(chunk, true));
+        }
+
+        assert_eq!(merged.count(), whole.count());
+        assert_eq!(merged.sum(), whole.sum());
+        assert_eq!(merged.values(), whole.values(), "values keep their order");
+        assert!((merged.variance() - whole.variance()).abs() < 1e-9);
+        assert_eq!(merged.most(), whole.most());
+        assert_eq!(merged.fewest(), whole.fewest());
+    }
+
+    #[test]
+    fn test_accumulator_does_not_overflow() {
+        let mut accumulator = Accumulator::new(false);
+        for _ in 0..4 {
+            accumulator.add(u64::MAX);
+        }
+
+        assert_eq!(accumulator.sum(), 4 * u64::MAX as u128);
+        assert_eq!(accumulator.average(), u64::MAX as f64);
+        assert_eq!(accumulator.variance(), 0.0);
+    }
+}
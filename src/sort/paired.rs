@@ -0,0 +1,525 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use super::{
+    metrics::ReportSettings,
+    statistics::{confidence_margin, normal_cdf, student_t_cdf},
+    ReportError, ShellsortResult, SorterResults, SHELLSORT_METRICS,
+};
+
+//Updates the differences of a candidate from the baseline a round at a time
+//(Welford), like Accumulator. The Wilcoxon test only needs the rank of every
+//magnitude, so instead of the differences themselves it keeps how often each
+//magnitude was a win and a loss, which grows with the distinct magnitudes
+//rather than with the rounds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DifferenceAccumulator {
+    count: u64,
+    sum: i128,
+    mean: f64,
+    m2: f64,
+    wins: u64,
+    ties: u64,
+    magnitudes: Option<BTreeMap<u64, (u64, u64)>>,
+}
+
+impl DifferenceAccumulator {
+    pub fn new(keep_magnitudes: bool) -> Self {
+        Self {
+            count: 0,
+            sum: 0,
+            mean: 0.0,
+            m2: 0.0,
+            wins: 0,
+            ties: 0,
+            magnitudes: if keep_magnitudes {
+                Some(BTreeMap::new())
+            } else {
+                None
+            },
+        }
+    }
+
+    pub fn add(&mut self, baseline: u64, candidate: u64) {
+        let difference = candidate as i128 - baseline as i128;
+
+        self.count += 1;
+        self.sum += difference;
+
+        let delta = difference as f64 - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (difference as f64 - self.mean);
+
+        if difference == 0 {
+            self.ties += 1;
+            return;
+        }
+
+        if difference < 0 {
+            self.wins += 1;
+        }
+
+        if let Some(ref mut magnitudes) = self.magnitudes {
+            let (wins, losses) = magnitudes.entry(baseline.abs_diff(candidate)).or_default();
+            if difference < 0 {
+                *wins += 1;
+            } else {
+                *losses += 1;
+            }
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    //Taken from the exact sum rather than the running mean.
+    pub fn average(&self) -> f64 {
+        self.sum as f64 / self.count as f64
+    }
+
+    pub fn sample_variance(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+
+        self.m2 / (self.count - 1) as f64
+    }
+
+    pub fn standard_error(&self) -> f64 {
+        (self.sample_variance() / self.count as f64).sqrt()
+    }
+
+    //The Wilcoxon p-value is NaN when the magnitudes were not kept.
+    pub fn difference(&self) -> Result<Difference, ReportError> {
+        if self.count == 0 {
+            return Err(ReportError::EmptyResults);
+        }
+
+        Ok(Difference {
+            average: self.average(),
+            stddev: (self.m2 / self.count as f64).sqrt(),
+            sample_stddev: self.sample_variance().sqrt(),
+            wins: self.wins as usize,
+            ties: self.ties as usize,
+            count: self.count as usize,
+            wilcoxon_p_value: self.magnitudes
+                .as_ref()
+                .map_or(f64::NAN, wilcoxon_p_value),
+        })
+    }
+}
+
+//Written to checkpoints as `count sum mean m2 wins ties`, followed by the
+//kept magnitudes as `[magnitude:wins:losses,...]`. f64 values are printed
+//so that they parse back exactly.
+impl fmt::Display for DifferenceAccumulator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {}",
+            self.count, self.sum, self.mean, self.m2, self.wins, self.ties,
+        )?;
+
+        if let Some(ref magnitudes) = self.magnitudes {
+            let magnitudes: Vec<String> = magnitudes
+                .iter()
+                .map(|(magnitude, (wins, losses))| format!("{}:{}:{}", magnitude, wins, losses))
+                .collect();
+            write!(f, " [{}]", magnitudes.join(","))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for DifferenceAccumulator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid differences '{}'.", s);
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 6 && fields.len() != 7 {
+            return Err(invalid());
+        }
+
+        let magnitudes = match fields.get(6) {
+            Some(field) => {
+                let list = field
+                    .strip_prefix('[')
+                    .and_then(|field| field.strip_suffix(']'))
+                    .ok_or_else(invalid)?;
+
+                let mut magnitudes = BTreeMap::new();
+                for entry in list.split(',').filter(|entry| !entry.is_empty()) {
+                    let counts: Vec<u64> = entry
+                        .split(':')
+                        .map(|count| count.parse::<u64>())
+                        .collect::<Result<Vec<u64>, _>>()
+                        .map_err(|_| invalid())?;
+
+                    let [magnitude, wins, losses] = counts[..] else {
+                        return Err(invalid());
+                    };
+                    magnitudes.insert(magnitude, (wins, losses));
+                }
+
+                Some(magnitudes)
+            },
+            None => None,
+        };
+
+        Ok(Self {
+            count: fields[0].parse().map_err(|_| invalid())?,
+            sum: fields[1].parse().map_err(|_| invalid())?,
+            mean: fields[2].parse().map_err(|_| invalid())?,
+            m2: fields[3].parse().map_err(|_| invalid())?,
+            wins: fields[4].parse().map_err(|_| invalid())?,
+            ties: fields[5].parse().map_err(|_| invalid())?,
+            magnitudes,
+        })
+    }
+}
+
+//Differences of the candidate from the baseline on the same arrays, so a
+//negative average means the candidate needed fewer operations.
+pub struct Difference {
+    pub average: f64,
+    pub stddev: f64,
+    pub sample_stddev: f64,
+    pub wins: usize,
+    pub ties: usize,
+    pub count: usize,
+    pub wilcoxon_p_value: f64,
+}
+
+impl Difference {
+    pub fn new(
+        baseline: impl Iterator<Item = u64>,
+        candidate: impl Iterator<Item = u64>,
+    ) -> Result<Self, ReportError> {
+        let mut accumulator = DifferenceAccumulator::new(true);
+        for (baseline, candidate) in baseline.zip(candidate) {
+            accumulator.add(baseline, candidate);
+        }
+
+        accumulator.difference()
+    }
+
+    pub fn standard_error(&self) -> f64 {
+        self.sample_stddev / (self.count as f64).sqrt()
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.count as f64
+    }
+
+    pub fn interval(&self, confidence: f64) -> Option<(f64, f64)> {
+        let margin = confidence_margin(confidence, self.standard_error(), self.count as u64)?;
+        Some((self.average - margin, self.average + margin))
+    }
+
+    pub fn t_statistic(&self) -> f64 {
+        self.average / self.standard_error()
+    }
+
+    //Two-sided p-value of the paired t-test that the average difference is 0.
+    pub fn t_test_p_value(&self) -> f64 {
+        if self.count < 2 {
+            return 1.0;
+        }
+
+        if self.standard_error() == 0.0 {
+            return if self.average == 0.0 { 1.0 } else { 0.0 };
+        }
+
+        let t = self.t_statistic().abs();
+        2.0 * (1.0 - student_t_cdf(t, (self.count - 1) as f64))
+    }
+}
+
+//Two-sided p-value of the Wilcoxon signed-rank test, using the normal
+//approximation with corrections for ties and continuity. Unlike the t-test
+//it does not assume the differences are normally distributed. Zero
+//differences are dropped, and the rest are given by how often each
+//magnitude was a win and a loss.
+fn wilcoxon_p_value(magnitudes: &BTreeMap<u64, (u64, u64)>) -> f64 {
+    //Tied magnitudes share the average of their ranks.
+    let mut ranked = 0.0;
+    let mut positive_ranks = 0.0;
+    let mut tie_correction = 0.0;
+    for &(wins, losses) in magnitudes.values() {
+        let tied = (wins + losses) as f64;
+        let rank = ranked + (tied + 1.0) / 2.0;
+
+        positive_ranks += rank * losses as f64;
+        tie_correction += tied * tied * tied - tied;
+        ranked += tied;
+    }
+
+    if ranked == 0.0 {
+        return 1.0;
+    }
+
+    let n = ranked;
+    let mean = n * (n + 1.0) / 4.0;
+    let variance = n * (n + 1.0) * (2.0 * n + 1.0) / 24.0 - tie_correction / 48.0;
+
+    if variance <= 0.0 {
+        return 1.0;
+    }
+
+    let distance = ((positive_ranks - mean).abs() - 0.5).max(0.0);
+    let z = distance / variance.sqrt();
+
+    (2.0 * (1.0 - normal_cdf(z))).min(1.0)
+}
+
+//The comparisons and moves of one candidate against the baseline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PairedDifferences {
+    pub comparisons: DifferenceAccumulator,
+    pub moves: DifferenceAccumulator,
+}
+
+impl PairedDifferences {
+    pub fn new() -> Self {
+        Self {
+            comparisons: DifferenceAccumulator::new(true),
+            moves: DifferenceAccumulator::new(true),
+        }
+    }
+
+    pub fn add(&mut self, baseline: &ShellsortResult, candidate: &ShellsortResult) {
+        self.comparisons.add(baseline.comparisons, candidate.comparisons);
+        self.moves.add(baseline.moves, candidate.moves);
+    }
+}
+
+impl Default for PairedDifferences {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//The results of candidates that sorted the same arrays in every round, each
+//with its differences from the first candidate. The first candidate is
+//compared with itself, so the differences line up with the candidates.
+pub struct PairedResults {
+    pub results: Vec<SorterResults>,
+    pub differences: Vec<PairedDifferences>,
+}
+
+impl PairedResults {
+    pub fn new(candidates: usize, keep_values: bool) -> Self {
+        Self {
+            results: (0..candidates)
+                .map(|_| SorterResults::with_metrics(
+                    "Shellsort".to_string(),
+                    SHELLSORT_METRICS,
+                    keep_values,
+                ))
+                .collect(),
+            differences: vec![PairedDifferences::new(); candidates],
+        }
+    }
+
+    pub fn add(&mut self, round: &[ShellsortResult]) {
+        let Some(baseline) = round.first() else {
+            return;
+        };
+
+        for ((results, differences), result) in self.results
+            .iter_mut()
+            .zip(self.differences.iter_mut())
+            .zip(round)
+        {
+            results.add(&[result.comparisons, result.moves]);
+            differences.add(baseline, result);
+        }
+    }
+
+    pub fn rounds(&self) -> u64 {
+        self.results.first().map_or(0, SorterResults::rounds)
+    }
+}
+
+//Tiny p-values lose all precision to 1 - cdf, so they are only bounded.
+pub fn format_p_value(p: f64) -> String {
+    if p < 1e-4 {
+        String::from("< 0.0001")
+    } else {
+        format!("{:.4}", p)
+    }
+}
+
+pub struct PairedReport {
+    pub comparisons: Difference,
+    pub moves: Difference,
+    pub confidence: f64,
+}
+
+impl PairedReport {
+    pub fn new(
+        differences: &PairedDifferences,
+        settings: &ReportSettings,
+    ) -> Result<Self, ReportError> {
+        settings.check()?;
+
+        Ok(Self {
+            comparisons: differences.comparisons.difference()?,
+            moves: differences.moves.difference()?,
+            confidence: settings.confidence,
+        })
+    }
+
+    pub fn differences(&self) -> [(&'static str, &Difference); 2] {
+        [("comparisons", &self.comparisons), ("moves", &self.moves)]
+    }
+
+    pub fn difference(&self, name: &str) -> Option<&Difference> {
+        self.differences()
+            .into_iter()
+            .find(|(metric, _)| *metric == name)
+            .map(|(_, difference)| difference)
+    }
+
+    pub fn print(&self) {
+        println!("Paired Difference Report:");
+        for (name, difference) in self.differences() {
+            self.print_difference(name, difference);
+        }
+    }
+
+    fn print_difference(&self, name: &str, difference: &Difference) {
+        println!("Average difference in {}: {:.2}", name, difference.average);
+        println!("Std Dev difference in {}: {:.2}", name, difference.stddev);
+        if let Some((low, high)) = difference.interval(self.confidence) {
+            println!(
+                "{}% CI of difference in {}: {:.2} to {:.2}",
+                (self.confidence * 1000.0).round() / 10.0,
+                name,
+                low,
+                high,
+            );
+        }
+        println!(
+            "Rounds with fewer {}: {} of {} ({} tied, {:.1}% win rate)",
+            name,
+            difference.wins,
+            difference.count,
+            difference.ties,
+            difference.win_rate() * 100.0,
+        );
+        println!(
+            "Paired t-test p-value for {}: {}",
+            name,
+            format_p_value(difference.t_test_p_value()),
+        );
+        println!(
+            "Wilcoxon signed-rank p-value for {}: {}",
+            name,
+            format_p_value(difference.wilcoxon_p_value),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_difference() {
+        let difference = Difference::new(
+            [10, 20, 30, 40].into_iter(),
+            [8, 20, 27, 41].into_iter(),
+        ).unwrap();
+
+        assert_eq!(difference.average, -1.0, "average of -2, 0, -3 and 1");
+        assert_eq!(difference.wins, 2, "two rounds were cheaper");
+        assert_eq!(difference.ties, 1, "one round tied");
+        assert_eq!(difference.count, 4);
+        assert!((difference.stddev - 1.5811388).abs() < 1e-6, "population stddev");
+        assert!((difference.sample_stddev - 1.8257419).abs() < 1e-6, "sample stddev");
+        assert_eq!(difference.win_rate(), 0.5);
+    }
+
+    #[test]
+    fn test_difference_accumulator_round_trip() {
+        let mut accumulator = DifferenceAccumulator::new(true);
+        for (baseline, candidate) in [(10, 8), (20, 20), (30, 27), (40, 41), (7, 5)] {
+            accumulator.add(baseline, candidate);
+        }
+
+        let text = accumulator.to_string();
+        assert!(text.ends_with(" [1:0:1,2:2:0,3:1:0]"), "{}", text);
+        assert_eq!(text.parse::<DifferenceAccumulator>(), Ok(accumulator.clone()));
+
+        let mut unranked = DifferenceAccumulator::new(false);
+        unranked.add(3, 1);
+        assert_eq!(unranked.to_string().parse::<DifferenceAccumulator>(), Ok(unranked.clone()));
+        assert!(unranked.difference().unwrap().wilcoxon_p_value.is_nan(), "no ranks");
+        assert!("1 2 3".parse::<DifferenceAccumulator>().is_err());
+    }
+
+    #[test]
+    fn test_significance() {
+        //Every round is 1 to 3 operations cheaper.
+        let baseline: Vec<u64> = (0..30).map(|i| 100 + i).collect();
+        let candidate: Vec<u64> = (0..30).map(|i| 100 + i - 1 - i % 3).collect();
+
+        let difference = Difference::new(
+            baseline.iter().copied(),
+            candidate.iter().copied(),
+        ).unwrap();
+
+        assert!(difference.t_test_p_value() < 1e-6, "clearly better");
+        assert!(difference.wilcoxon_p_value < 1e-4, "clearly better");
+        let (low, high) = difference.interval(0.95).unwrap();
+        assert!(low < -2.0 && high < -1.5 && high > -2.0, "{} to {}", low, high);
+
+        //Wins and losses of the same sizes cancel out.
+        let baseline = [10, 10, 10, 10, 10, 10];
+        let candidate = [9, 11, 8, 12, 7, 13];
+        let difference = Difference::new(
+            baseline.into_iter(),
+            candidate.into_iter(),
+        ).unwrap();
+
+        assert!((difference.t_test_p_value() - 1.0).abs() < 1e-9, "no evidence either way");
+        assert!(difference.wilcoxon_p_value > 0.9, "no evidence either way");
+    }
+
+    #[test]
+    fn test_wilcoxon_small_sample() {
+        //n = 8 with all differences negative and distinct: W+ = 0, the
+        //normal approximation gives z = (18 - 0.5) / 7.1414 = 2.4505.
+        let p = Difference::new([10; 8].into_iter(), (2..10).rev())
+            .unwrap()
+            .wilcoxon_p_value;
+        assert!((p - 0.014266).abs() < 1e-4, "p is {}", p);
+        assert_eq!(wilcoxon_p_value(&BTreeMap::new()), 1.0, "all tied");
+    }
+
+    #[test]
+    fn test_difference_empty() {
+        let difference = Difference::new([].into_iter(), [].into_iter());
+
+        assert!(matches!(difference, Err(ReportError::EmptyResults)));
+    }
+
+    #[test]
+    fn test_paired_report_checks_confidence() {
+        let mut differences = PairedDifferences::new();
+        differences.add(
+            &ShellsortResult { comparisons: 10, moves: 5 },
+            &ShellsortResult { comparisons: 12, moves: 6 },
+        );
+        let settings = ReportSettings { confidence: 2.0, ..ReportSettings::default() };
+
+        assert!(matches!(
+            PairedReport::new(&differences, &settings),
+            Err(ReportError::InvalidConfidence(_)),
+        ));
+        assert!(PairedReport::new(&differences, &ReportSettings::default()).is_ok());
+    }
+}
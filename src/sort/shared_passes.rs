@@ -0,0 +1,91 @@
+use super::shellsort::{shellsort_pass, ShellsortResult};
+
+//Sorts a copy of `a` with every candidate gap sequence. Passes run from the
+//largest gap down, so candidates that agree on their largest gaps share the
+//array and counters left by those passes instead of repeating them.
+pub fn shellsort_shared<T: PartialOrd + Copy, G: AsRef<[usize]>>(
+    a: &mut [T],
+    candidates: &[G],
+) -> Vec<ShellsortResult> {
+    let mut results = vec![ShellsortResult { comparisons: 0, moves: 0 }; candidates.len()];
+    let indices: Vec<usize> = (0..candidates.len()).collect();
+
+    let start = ShellsortResult { comparisons: 0, moves: 0 };
+    shared_passes(a, candidates, &indices, 0, start, &mut results);
+
+    results
+}
+
+fn shared_passes<T: PartialOrd + Copy, G: AsRef<[usize]>>(
+    a: &mut [T],
+    candidates: &[G],
+    indices: &[usize],
+    depth: usize,
+    so_far: ShellsortResult,
+    results: &mut [ShellsortResult],
+) {
+    //Group the candidates by the gap of their next pass, keeping the order
+    //in which the gaps first appear.
+    let mut groups: Vec<(usize, Vec<usize>)> = Vec::new();
+    for &i in indices {
+        let gaps = candidates[i].as_ref();
+        if depth >= gaps.len() {
+            results[i] = so_far;
+            continue;
+        }
+
+        let gap = gaps[gaps.len() - 1 - depth];
+        match groups.iter_mut().find(|(group_gap, _)| *group_gap == gap) {
+            Some((_, members)) => members.push(i),
+            None => groups.push((gap, vec![i])),
+        }
+    }
+
+    let last = groups.len().saturating_sub(1);
+    for (g, (gap, members)) in groups.into_iter().enumerate() {
+        let mut result = so_far;
+
+        //The last group may run in place, every other one needs a snapshot
+        //so that its siblings still start from the shared state.
+        if g == last {
+            shellsort_pass(a, gap, &mut result);
+            shared_passes(a, candidates, &members, depth + 1, result, results);
+        } else {
+            let mut snapshot = a.to_vec();
+            shellsort_pass(&mut snapshot, gap, &mut result);
+            shared_passes(&mut snapshot, candidates, &members, depth + 1, result, results);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::shellsort::shellsort;
+
+    #[test]
+    fn test_shellsort_shared() {
+        let a: Vec<usize> = vec![
+            17, 3, 25, 8, 0, 29, 12, 21, 5, 14, 27, 1, 19, 10, 23,
+            6, 28, 15, 2, 24, 9, 18, 4, 26, 11, 20, 7, 16, 22, 13,
+        ];
+
+        let candidates: Vec<Vec<usize>> = vec![
+            vec![1, 4, 13],
+            vec![1, 3, 13],
+            vec![1, 2, 5, 13],
+            vec![1, 4, 10, 23],
+            vec![1],
+            vec![1, 4, 13],
+        ];
+
+        let results = shellsort_shared(&mut a.clone(), &candidates);
+
+        for (gaps, shared) in candidates.iter().zip(results.iter()) {
+            let single = shellsort(&mut a.clone(), gaps);
+
+            assert_eq!(shared.comparisons, single.comparisons, "comparisons for {:?}", gaps);
+            assert_eq!(shared.moves, single.moves, "moves for {:?}", gaps);
+        }
+    }
+}
@@ -1,15 +1,20 @@
-use super::ReportError;
+use std::cmp::Ordering;
 
+use crate::gap_sequences::Gaps;
 
-#[derive(Debug)]
+use super::Sorter;
+
+pub const SHELLSORT_METRICS: &[&str] = &["comparisons", "moves"];
+
+#[derive(Debug, Clone, Copy)]
 pub struct ShellsortResult {
     pub comparisons: u64,
     pub moves: u64
 }
 
 pub fn shellsort<T: PartialOrd + Copy>(
-    a: &mut Vec<T>,
-    gaps: &Vec<usize>,
+    a: &mut [T],
+    gaps: &[usize],
 ) -> ShellsortResult {
     let mut result = ShellsortResult {
         comparisons: 0,
@@ -17,134 +22,126 @@ pub fn shellsort<T: PartialOrd + Copy>(
     };
 
     for gap in gaps.iter().rev() {
-        for i in *gap..a.len() {
-            let mut j = i;
-            let temp = a[i];
-
-            while j >= *gap {
-                result.comparisons += 1;
-                if a[j - gap] <= temp {
-                    break;
-                }
+        shellsort_pass(a, *gap, &mut result);
+    }
 
-                result.moves += 1;
-                a[j] = a[j - gap];
+    result
+}
 
-                j -= gap;
+pub fn shellsort_pass<T: PartialOrd + Copy>(
+    a: &mut [T],
+    gap: usize,
+    result: &mut ShellsortResult,
+) {
+    for i in gap..a.len() {
+        let mut j = i;
+        let temp = a[i];
+
+        while j >= gap {
+            result.comparisons += 1;
+            if a[j - gap] <= temp {
+                break;
             }
 
             result.moves += 1;
-            a[j] = temp;
+            a[j] = a[j - gap];
+
+            j -= gap;
         }
-    }
 
-    result
+        result.moves += 1;
+        a[j] = temp;
+    }
 }
 
-pub struct ShellsortReport {
-    pub average_comparisons: f64,
-    pub average_moves: f64,
-    pub stddev_comparisons: f64,
-    pub stddev_moves: f64,
-    pub fewest_comparisons: u64,
-    pub fewest_moves: u64,
-    pub most_comparisons: u64,
-    pub most_moves: u64,
+pub struct ShellsortSorter {
+    pub gaps: Vec<usize>,
 }
 
-impl ShellsortReport {
-    pub fn print(&self) {
-        println!("Shellsort Report:");
-        println!("Average comparisons: {:.2}", self.average_comparisons);
-        println!("Std Dev comparisons: {:.2}", self.stddev_comparisons);
-        println!("Most comparisons: {}", self.most_comparisons);
-        println!("Fewest comparisons: {}", self.fewest_comparisons);
-        println!("Average moves: {:.2}", self.average_moves);
-        println!("Std Dev moves: {:.2}", self.stddev_moves);
-        println!("Most moves: {}", self.most_moves);
-        println!("Fewest moves: {}", self.fewest_moves);
+impl Sorter for ShellsortSorter {
+    fn name(&self) -> String {
+        "Shellsort".to_string()
     }
-}
 
-impl TryFrom<Vec<ShellsortResult>> for ShellsortReport {
-    type Error = ReportError;
-
-    fn try_from(results: Vec<ShellsortResult>) -> Result<Self, ReportError> {
-        if results.is_empty() {
-            return Err(ReportError::EmptyResults);
-        }
-
-        let mut report = Self {
-            average_comparisons: 0.0,
-            average_moves: 0.0,
-            stddev_comparisons: 0.0,
-            stddev_moves: 0.0,
-            fewest_comparisons: u64::MAX,
-            fewest_moves: u64::MAX,
-            most_comparisons: 0,
-            most_moves: 0,
-        };
-
-        let mut sum_comparisons: u64 = 0;
-        let mut sum_moves: u64 = 0;
-
-        for result in results.iter() {
-            sum_comparisons += result.comparisons as u64;
-            sum_moves += result.moves as u64;
-
-            if result.comparisons < report.fewest_comparisons {
-                report.fewest_comparisons = result.comparisons;
-            }
+    fn metrics(&self) -> &'static [&'static str] {
+        SHELLSORT_METRICS
+    }
 
-            if result.moves < report.fewest_moves {
-                report.fewest_moves = result.moves;
-            }
+    fn sort(&self, a: &mut [usize]) -> Vec<u64> {
+        let result = shellsort(a, &self.gaps);
+        vec![result.comparisons, result.moves]
+    }
+}
 
-            if result.comparisons > report.most_comparisons {
-                report.most_comparisons = result.comparisons;
-            }
+//Sorts with a comparator like `sort_unstable_by`. Elements are swapped
+//into place rather than copied, so they need not be Copy or Clone. The
+//counts are the same as those of shellsort on the same input.
+pub fn shellsort_by<T, G, F>(
+    a: &mut [T],
+    gaps: &G,
+    mut compare: F,
+) -> ShellsortResult
+where
+    G: Gaps + ?Sized,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut result = ShellsortResult {
+        comparisons: 0,
+        moves: 0,
+    };
 
-            if result.moves > report.most_moves {
-                report.most_moves = result.moves;
-            }
-        }
+    for &gap in gaps.gaps(a.len()).iter().rev() {
+        for i in gap..a.len() {
+            let mut j = i;
 
-        let count = results.len() as f64;
-        report.average_comparisons = sum_comparisons as f64 / count;
-        report.average_moves = sum_moves as f64 / count;
+            while j >= gap {
+                result.comparisons += 1;
+                if compare(&a[j - gap], &a[j]) != Ordering::Greater {
+                    break;
+                }
 
-        let mut sum_squared_diff_comparisons: f64 = 0.0;
-        let mut sum_squared_diff_moves: f64 = 0.0;
+                //One swap does the work of shifting the larger element up.
+                result.moves += 1;
+                a.swap(j - gap, j);
 
-        for result in results.iter() {
-            let diff_comparisons = result.comparisons as f64 - report.average_comparisons;
-            sum_squared_diff_comparisons += diff_comparisons * diff_comparisons;
+                j -= gap;
+            }
 
-            let diff_moves = result.moves as f64 - report.average_moves;
-            sum_squared_diff_moves += diff_moves * diff_moves;
+            //Counted as the move that places the element in its slot.
+            result.moves += 1;
         }
-
-        report.stddev_comparisons = (sum_squared_diff_comparisons / count).sqrt();
-        report.stddev_moves = (sum_squared_diff_moves / count).sqrt();
-
-        Ok(report)
     }
+
+    result
 }
 
+pub fn shellsort_by_key<T, K, G, F>(
+    a: &mut [T],
+    gaps: &G,
+    mut key: F,
+) -> ShellsortResult
+where
+    K: Ord,
+    G: Gaps + ?Sized,
+    F: FnMut(&T) -> K,
+{
+    shellsort_by(a, gaps, |x, y| key(x).cmp(&key(y)))
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::gap_sequences::GapSequence;
 
     #[test]
     fn test_shellsort() {
         let mut a = vec![3, 2, 1, 4, 5];
-        shellsort(&mut a, &vec![1, 4, 10, 23, 57]);
+        shellsort(&mut a, &[1, 4, 10, 23, 57]);
 
         assert_eq!(a, vec![1, 2, 3, 4, 5], "sort 5 values");
 
         let mut a = vec![3, 2, 1, 4, 10, 5, 9, 8, 7, 6];
-        shellsort(&mut a, &vec![1, 4, 10, 23, 57]);
+        shellsort(&mut a, &[1, 4, 10, 23, 57]);
 
         assert_eq!(a, vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10], "sort 10 values");
     }
@@ -152,7 +149,7 @@ mod tests {
     #[test]
     fn test_shellsort_reverse() {
         let mut a = vec![10, 9, 8, 7, 6, 5, 4, 3, 2, 1];
-        shellsort(&mut a, &vec![1, 4, 10, 23, 57]);
+        shellsort(&mut a, &[1, 4, 10, 23, 57]);
 
         assert_eq!(a, vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
     }
@@ -160,8 +157,37 @@ mod tests {
     #[test]
     fn test_shellsort_duplicates() {
         let mut a = vec![3, 5, 5, 5, 2, 1, 4, 4, 5, 5];
-        shellsort(&mut a, &vec![1, 4, 10, 23, 57]);
+        shellsort(&mut a, &[1, 4, 10, 23, 57]);
 
         assert_eq!(a, vec![1, 2, 3, 4, 4, 5, 5, 5, 5, 5]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_shellsort_by_matches_shellsort() {
+        let a = vec![3, 5, 5, 5, 2, 1, 4, 4, 5, 5, 0, 9, 7];
+
+        let mut expected = a.clone();
+        let expected_result = shellsort(&mut expected, &[1, 4, 10]);
+
+        let mut sorted = a.clone();
+        let result = shellsort_by(&mut sorted, &[1, 4, 10], |x, y| x.cmp(y));
+
+        assert_eq!(sorted, expected);
+        assert_eq!(result.comparisons, expected_result.comparisons, "same comparisons");
+        assert_eq!(result.moves, expected_result.moves, "same moves");
+    }
+
+    #[test]
+    fn test_shellsort_by_non_copy() {
+        let mut a: Vec<String> = ["pear", "fig", "apple", "cherries", "banana"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        shellsort_by(&mut a[..], &GapSequence::Knuth1973, |x, y| y.cmp(x));
+        assert_eq!(a, vec!["pear", "fig", "cherries", "banana", "apple"], "descending");
+
+        shellsort_by_key(&mut a, &vec![1, 2], |s| s.len());
+        assert_eq!(a, vec!["fig", "pear", "apple", "banana", "cherries"], "by length");
+    }
+}
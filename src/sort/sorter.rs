@@ -0,0 +1,95 @@
+use super::{
+    metrics::{Accumulator, MetricReport, ReportSettings},
+    ReportError,
+};
+
+//An instrumented sorting algorithm. Each call to sort returns one value for
+//every metric, in the order given by metrics.
+pub trait Sorter: Sync {
+    fn name(&self) -> String;
+    fn metrics(&self) -> &'static [&'static str];
+    fn sort(&self, a: &mut [usize]) -> Vec<u64>;
+}
+
+//The statistics of every metric of one sorter, updated as rounds finish.
+pub struct SorterResults {
+    pub name: String,
+    pub metrics: &'static [&'static str],
+    pub accumulators: Vec<Accumulator>,
+}
+
+impl SorterResults {
+    //Keeping the values of every round allows medians and percentiles, at
+    //the cost of memory growing with the rounds.
+    pub fn new(sorter: &dyn Sorter, keep_values: bool) -> Self {
+        Self::with_metrics(sorter.name(), sorter.metrics(), keep_values)
+    }
+
+    pub fn with_metrics(
+        name: String,
+        metrics: &'static [&'static str],
+        keep_values: bool,
+    ) -> Self {
+        Self {
+            name,
+            metrics,
+            accumulators: vec![Accumulator::new(keep_values); metrics.len()],
+        }
+    }
+
+    pub fn add(&mut self, values: &[u64]) {
+        for (accumulator, &value) in self.accumulators.iter_mut().zip(values) {
+            accumulator.add(value);
+        }
+    }
+
+    pub fn merge(&mut self, other: &SorterResults) {
+        for (accumulator, other) in self.accumulators.iter_mut().zip(other.accumulators.iter()) {
+            accumulator.merge(other);
+        }
+    }
+
+    pub fn rounds(&self) -> u64 {
+        self.accumulators.first().map_or(0, Accumulator::count)
+    }
+}
+
+pub struct SorterReport {
+    pub name: String,
+    pub metrics: Vec<MetricReport>,
+}
+
+impl SorterReport {
+    pub fn new(results: &SorterResults, settings: &ReportSettings) -> Result<Self, ReportError> {
+        let metrics = results.metrics
+            .iter()
+            .zip(results.accumulators.iter())
+            .map(|(&name, accumulator)| MetricReport::new(name, accumulator, settings))
+            .collect::<Result<Vec<MetricReport>, ReportError>>()?;
+
+        Ok(Self {
+            name: results.name.clone(),
+            metrics,
+        })
+    }
+
+    pub fn metric(&self, name: &str) -> Option<&MetricReport> {
+        self.metrics.iter().find(|metric| metric.name == name)
+    }
+
+    pub fn print(&self) {
+        println!("{} Report:", self.name);
+
+        for metric in self.metrics.iter() {
+            metric.print();
+        }
+    }
+}
+
+impl TryFrom<&SorterResults> for SorterReport {
+    type Error = ReportError;
+
+    fn try_from(results: &SorterResults) -> Result<Self, ReportError> {
+        Self::new(results, &ReportSettings::default())
+    }
+}
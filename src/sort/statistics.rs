@@ -0,0 +1,216 @@
+use std::f64::consts::{LN_2, PI};
+
+//Lanczos approximation with g = 7.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        //Reflection formula.
+        return (PI / (PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| sum + c / (x + i as f64 + 1.0));
+
+    0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+//log2(n!), the fewest comparisons any sort needs on average over all n!
+//permutations.
+pub fn log2_factorial(n: usize) -> f64 {
+    if n < 2 {
+        return 0.0;
+    }
+
+    ln_gamma(n as f64 + 1.0) / LN_2
+}
+
+//The regularized incomplete beta function I_x(a, b).
+pub fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b)
+        + a * x.ln()
+        + b * (1.0 - x).ln())
+        .exp();
+
+    //The continued fraction converges quickly only on this side.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+//Lentz's method for the continued fraction of the incomplete beta function.
+fn beta_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let not_tiny = |value: f64| if value.abs() < TINY { TINY } else { value };
+
+    let mut c = 1.0;
+    let mut d = 1.0 / not_tiny(1.0 - (a + b) * x / (a + 1.0));
+    let mut h = d;
+
+    for m in 1..=300 {
+        let m = m as f64;
+
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / not_tiny(1.0 + even * d);
+        c = not_tiny(1.0 + even / c);
+        h *= d * c;
+
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / not_tiny(1.0 + odd * d);
+        c = not_tiny(1.0 + odd / c);
+        h *= d * c;
+
+        if (d * c - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+
+    h
+}
+
+pub fn student_t_cdf(t: f64, degrees_of_freedom: f64) -> f64 {
+    let x = degrees_of_freedom / (degrees_of_freedom + t * t);
+    let tail = 0.5 * incomplete_beta(degrees_of_freedom / 2.0, 0.5, x);
+
+    if t > 0.0 {
+        1.0 - tail
+    } else {
+        tail
+    }
+}
+
+//Inverts student_t_cdf by bisection, which is plenty fast for reports.
+//Only probabilities strictly between 0 and 1 have a quantile.
+pub fn student_t_quantile(p: f64, degrees_of_freedom: f64) -> f64 {
+    if !(p > 0.0 && p < 1.0 && degrees_of_freedom > 0.0) {
+        return f64::NAN;
+    }
+
+    let mut low = -1.0;
+    let mut high = 1.0;
+
+    while student_t_cdf(low, degrees_of_freedom) > p {
+        low *= 2.0;
+    }
+
+    while student_t_cdf(high, degrees_of_freedom) < p {
+        high *= 2.0;
+    }
+
+    for _ in 0..200 {
+        let middle = (low + high) / 2.0;
+        if student_t_cdf(middle, degrees_of_freedom) < p {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+
+    (low + high) / 2.0
+}
+
+//Abramowitz and Stegun 7.1.26, accurate to about 1e-7.
+pub fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / 2f64.sqrt();
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let polynomial = t * (0.254_829_592
+        + t * (-0.284_496_736
+        + t * (1.421_413_741
+        + t * (-1.453_152_027
+        + t * 1.061_405_429))));
+    let erf = 1.0 - polynomial * (-x * x).exp();
+
+    if z >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+//Half the width of the two-sided confidence interval of a mean, which needs
+//at least two values and a confidence strictly between 0 and 1.
+pub fn confidence_margin(confidence: f64, standard_error: f64, count: u64) -> Option<f64> {
+    if count < 2 || !(confidence > 0.0 && confidence < 1.0) {
+        return None;
+    }
+
+    let quantile = student_t_quantile(0.5 + confidence / 2.0, (count - 1) as f64);
+    Some(quantile * standard_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tolerance: f64) -> bool {
+        (a - b).abs() < tolerance
+    }
+
+    #[test]
+    fn test_student_t_quantile() {
+        //Values from a standard t table.
+        assert!(close(student_t_quantile(0.975, 1.0), 12.706, 1e-3));
+        assert!(close(student_t_quantile(0.975, 10.0), 2.228, 1e-3));
+        assert!(close(student_t_quantile(0.995, 30.0), 2.750, 1e-3));
+        assert!(close(student_t_quantile(0.975, 1e6), 1.960, 1e-3), "approaches normal");
+        assert!(close(student_t_quantile(0.025, 10.0), -2.228, 1e-3), "symmetric");
+
+        assert!(student_t_quantile(1.0, 10.0).is_nan(), "no quantile at 1");
+        assert!(student_t_quantile(1.5, 10.0).is_nan(), "not a probability");
+        assert!(student_t_quantile(0.975, 0.0).is_nan(), "no degrees of freedom");
+    }
+
+    #[test]
+    fn test_confidence_margin() {
+        assert!(close(confidence_margin(0.95, 1.0, 11).unwrap(), 2.228, 1e-3));
+        assert!(confidence_margin(0.95, 1.0, 1).is_none(), "one value");
+        assert!(confidence_margin(0.95, 1.0, 0).is_none(), "no values");
+        assert!(confidence_margin(1.5, 1.0, 10).is_none(), "confidence above 1");
+    }
+
+    #[test]
+    fn test_log2_factorial() {
+        assert_eq!(log2_factorial(1), 0.0);
+        assert!(close(log2_factorial(5), 120f64.log2(), 1e-9));
+        let exact: f64 = (2..=1000).map(|i| (i as f64).log2()).sum();
+        assert!(close(log2_factorial(1000), exact, 1e-6));
+    }
+
+    #[test]
+    fn test_normal_cdf() {
+        assert!(close(normal_cdf(0.0), 0.5, 1e-7));
+        assert!(close(normal_cdf(1.959964), 0.975, 1e-6));
+        assert!(close(normal_cdf(-1.0), 0.158655, 1e-6));
+    }
+
+    #[test]
+    fn test_student_t_cdf() {
+        assert!(close(student_t_cdf(0.0, 5.0), 0.5, 1e-12));
+        assert!(close(student_t_cdf(2.015, 5.0), 0.95, 1e-4));
+        assert!(close(student_t_cdf(-2.015, 5.0), 0.05, 1e-4));
+    }
+}
@@ -0,0 +1,453 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    gap_sequences::GapSequence,
+    sort::{
+        perform_rounds, perform_rounds_parallel, Distribution, Experiment, MaxDistance,
+        Normalized, QuicksortSorter, ShellsortSorter, Sorter, SorterResults,
+    },
+};
+
+//Lengths to sweep over, written as `start..end xFACTOR` for a geometric range
+//or `start..end +STEP` for a linear one. Both ends are included, and a range
+//without a step doubles the length.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lengths(pub Vec<usize>);
+
+impl FromStr for Lengths {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid lengths '{}'.", s);
+
+        let (range, step) = match s.trim().split_once(char::is_whitespace) {
+            Some((range, step)) => (range, step.trim()),
+            None => (s.trim(), "x2"),
+        };
+
+        let (start, end) = range.split_once("..").ok_or_else(invalid)?;
+        let start: usize = start.trim().parse().map_err(|_| invalid())?;
+        let end: usize = end.trim().parse().map_err(|_| invalid())?;
+
+        if start < 2 || end < start {
+            return Err(format!("Lengths must be at least 2 and increasing, not '{}'.", s));
+        }
+
+        let mut lengths = vec![start];
+
+        if let Some(factor) = step.strip_prefix('x') {
+            let factor: f64 = factor.parse().map_err(|_| invalid())?;
+            if factor.is_nan() || factor <= 1.0 {
+                return Err(invalid());
+            }
+
+            loop {
+                let last = *lengths.last().unwrap();
+                let next = ((last as f64 * factor).round() as usize).max(last + 1);
+                if next > end {
+                    break;
+                }
+                lengths.push(next);
+            }
+        } else if let Some(increment) = step.strip_prefix('+') {
+            let increment: usize = increment.parse().map_err(|_| invalid())?;
+            if increment == 0 {
+                return Err(invalid());
+            }
+
+            lengths.extend((start..=end).step_by(increment).skip(1));
+        } else {
+            return Err(invalid());
+        }
+
+        Ok(Lengths(lengths))
+    }
+}
+
+//Averages at one length. Quicksort makes no moves, so it has none.
+pub struct SweepPoint {
+    pub length: usize,
+    pub comparisons: f64,
+    pub moves: Option<f64>,
+}
+
+impl SweepPoint {
+    fn new(length: usize, results: &SorterResults) -> Self {
+        let average = |name: &str| {
+            results.metrics
+                .iter()
+                .position(|&metric| metric == name)
+                .map(|i| results.accumulators[i].average())
+        };
+
+        Self {
+            length,
+            comparisons: average("comparisons").unwrap_or(f64::NAN),
+            moves: average("moves"),
+        }
+    }
+}
+
+//The name is the gap sequence for Shellsort, or the sorter otherwise.
+pub struct SweepSeries {
+    pub name: String,
+    pub sorter: String,
+    pub points: Vec<SweepPoint>,
+}
+
+impl SweepSeries {
+    pub fn comparisons(&self) -> Vec<(f64, f64)> {
+        self.points.iter().map(|p| (p.length as f64, p.comparisons)).collect()
+    }
+
+    pub fn moves(&self) -> Vec<(f64, f64)> {
+        self.points
+            .iter()
+            .filter_map(|p| Some((p.length as f64, p.moves?)))
+            .collect()
+    }
+
+    pub fn print(&self) {
+        if self.name == self.sorter {
+            println!("Sweep Report for {}", self.name);
+        } else {
+            println!("Sweep Report for gap sequence: {}", self.name);
+        }
+
+        println!(
+            "{:>10}  {:>16}  {:>12}  {:>12}  {:>16}  {:>10}",
+            "Length", "Comparisons", "/ log2(n!)", "/ n log2 n", "Moves", "/ n",
+        );
+        for point in self.points.iter() {
+            let comparisons = Normalized::new("comparisons", point.comparisons, point.length);
+            let moves = point.moves.map(|moves| {
+                let per_n = Normalized::new("moves", moves, point.length).per_n;
+                (moves, per_n.unwrap_or(f64::NAN))
+            });
+
+            print!(
+                "{:>10}  {:>16.2}  {:>12.4}  {:>12.4}",
+                point.length,
+                point.comparisons,
+                comparisons.per_log2_factorial.unwrap_or(f64::NAN),
+                comparisons.per_n_log2_n.unwrap_or(f64::NAN),
+            );
+            match moves {
+                Some((moves, per_n)) => println!("  {:>16.2}  {:>10.4}", moves, per_n),
+                None => println!("  {:>16}  {:>10}", "-", "-"),
+            }
+        }
+
+        let mut metrics = vec![("comparisons", self.comparisons())];
+        if self.points.iter().all(|point| point.moves.is_some()) {
+            metrics.push(("moves", self.moves()));
+        }
+
+        for (name, points) in metrics {
+            match (Model::Power.fit(&points), Model::NLogN.fit(&points)) {
+                (Some(power), Some(n_log_n)) => {
+                    println!("Fit of {}: {}", name, power);
+                    println!("Fit of {}: {}", name, n_log_n);
+                },
+                _ => println!("Fit of {}: needs at least 2 lengths", name),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Model {
+    //c * n^e
+    Power,
+    //c * n * log2(n)^e
+    NLogN,
+}
+
+pub struct Fit {
+    pub model: Model,
+    pub coefficient: f64,
+    pub exponent: f64,
+    pub r_squared: f64,
+}
+
+impl Fit {
+    pub fn predict(&self, n: f64) -> f64 {
+        match self.model {
+            Model::Power => self.coefficient * n.powf(self.exponent),
+            Model::NLogN => self.coefficient * n * n.log2().powf(self.exponent),
+        }
+    }
+}
+
+impl fmt::Display for Fit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.model {
+            Model::Power => write!(f, "{:.4} * n^{:.4}", self.coefficient, self.exponent)?,
+            Model::NLogN => {
+                write!(f, "{:.4} * n * log2(n)^{:.4}", self.coefficient, self.exponent)?
+            },
+        }
+
+        write!(f, " (R^2 {:.6})", self.r_squared)
+    }
+}
+
+impl Model {
+    //Both models are straight lines after taking logarithms, so they are fit
+    //with least squares on ln(y) = ln(c) + e * ln(n), or on
+    //ln(y / n) = ln(c) + e * ln(log2(n)).
+    pub fn fit(&self, points: &[(f64, f64)]) -> Option<Fit> {
+        let transformed: Vec<(f64, f64)> = points
+            .iter()
+            .filter(|&&(n, y)| n >= 2.0 && y > 0.0)
+            .map(|&(n, y)| match self {
+                Model::Power => (n.ln(), y.ln()),
+                Model::NLogN => (n.log2().ln(), (y / n).ln()),
+            })
+            .collect();
+
+        let (slope, intercept, r_squared) = least_squares(&transformed)?;
+
+        Some(Fit {
+            model: *self,
+            coefficient: intercept.exp(),
+            exponent: slope,
+            r_squared,
+        })
+    }
+}
+
+//Returns the slope, intercept and coefficient of determination.
+fn least_squares(points: &[(f64, f64)]) -> Option<(f64, f64, f64)> {
+    if points.len() < 2 {
+        return None;
+    }
+
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|&(x, _)| x).sum::<f64>() / count;
+    let mean_y = points.iter().map(|&(_, y)| y).sum::<f64>() / count;
+
+    let sxx: f64 = points.iter().map(|&(x, _)| (x - mean_x) * (x - mean_x)).sum();
+    let sxy: f64 = points.iter().map(|&(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let syy: f64 = points.iter().map(|&(_, y)| (y - mean_y) * (y - mean_y)).sum();
+
+    if sxx == 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    let r_squared = if syy == 0.0 { 1.0 } else { sxy * sxy / (sxx * syy) };
+
+    Some((slope, intercept, r_squared))
+}
+
+pub struct SweepParameters {
+    //Every length of the sweep replaces the length of the experiment.
+    pub experiment: Experiment,
+    //Bounded swaps as far as the array is long follow the length of the sweep.
+    pub max_distance: MaxDistance,
+    pub quicksort: bool,
+    pub threads: usize,
+}
+
+impl SweepParameters {
+    pub fn distribution(&self, length: usize) -> Distribution {
+        match self.experiment.distribution {
+            Distribution::BoundedSwaps { probability, .. } => Distribution::BoundedSwaps {
+                max_distance: self.max_distance.at(length),
+                probability,
+            },
+            ref distribution => distribution.clone(),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match (&self.experiment.distribution, self.max_distance) {
+            (Distribution::BoundedSwaps { probability, .. }, MaxDistance::Length) => format!(
+                "permutations shuffled with maximum swap distance of the length \
+                and {:.1}% probability of each swap",
+                probability * 100.0,
+            ),
+            (distribution, _) => distribution.describe(),
+        }
+    }
+}
+
+//Sorts with every sequence at every length, all sorters sharing the same
+//arrays at each length. The gaps are generated again for every length.
+pub fn perform_sweep(
+    parameters: &SweepParameters,
+    lengths: &Lengths,
+    sequences: &[GapSequence],
+) -> Result<Vec<SweepSeries>, String> {
+    let mut series: Vec<SweepSeries> = Vec::new();
+
+    for &length in lengths.0.iter() {
+        let experiment = Experiment {
+            length,
+            distribution: parameters.distribution(length),
+            ..parameters.experiment.clone()
+        };
+
+        let shellsorts: Vec<ShellsortSorter> = sequences
+            .iter()
+            .map(|sequence| ShellsortSorter { gaps: sequence.to_vec(length) })
+            .collect();
+
+        let mut sorters: Vec<&dyn Sorter> = shellsorts
+            .iter()
+            .map(|shellsort| shellsort as &dyn Sorter)
+            .collect();
+        if parameters.quicksort {
+            sorters.push(&QuicksortSorter);
+        }
+
+        let results = if parameters.threads > 0 {
+            perform_rounds_parallel(&experiment, &sorters, false, parameters.threads)
+        } else {
+            perform_rounds(&experiment, &sorters, false)
+        }?;
+
+        if series.is_empty() {
+            series = results
+                .iter()
+                .enumerate()
+                .map(|(i, results)| SweepSeries {
+                    name: sequences.get(i).map_or(results.name.clone(), GapSequence::to_string),
+                    sorter: results.name.clone(),
+                    points: Vec::with_capacity(lengths.0.len()),
+                })
+                .collect();
+        }
+
+        for (series, results) in series.iter_mut().zip(results.iter()) {
+            series.points.push(SweepPoint::new(length, results));
+        }
+    }
+
+    Ok(series)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::{Distribution, RoundMode};
+
+    #[test]
+    fn test_lengths_from_str() {
+        assert_eq!(Lengths::from_str("100..1000 x2"), Ok(Lengths(vec![100, 200, 400, 800])));
+        assert_eq!(Lengths::from_str("100..800"), Ok(Lengths(vec![100, 200, 400, 800])));
+        assert_eq!(Lengths::from_str("10..40 +10"), Ok(Lengths(vec![10, 20, 30, 40])));
+        assert_eq!(Lengths::from_str("2..4 x1.1"), Ok(Lengths(vec![2, 3, 4])), "always grows");
+        assert!(Lengths::from_str("100..10").is_err());
+        assert!(Lengths::from_str("1..10").is_err());
+        assert!(Lengths::from_str("10..100 x1").is_err());
+        assert!(Lengths::from_str("10..100 *2").is_err());
+    }
+
+    #[test]
+    fn test_exact_fits() {
+        let lengths: [f64; 4] = [10.0, 100.0, 1000.0, 10000.0];
+
+        let points: Vec<(f64, f64)> = lengths.iter().map(|&n| (n, 3.0 * n.powf(1.5))).collect();
+        let fit = Model::Power.fit(&points).unwrap();
+        assert!((fit.exponent - 1.5).abs() < 1e-9, "exponent {}", fit.exponent);
+        assert!((fit.coefficient - 3.0).abs() < 1e-9, "coefficient {}", fit.coefficient);
+        assert!((fit.r_squared - 1.0).abs() < 1e-9);
+
+        let points: Vec<(f64, f64)> = lengths
+            .iter()
+            .map(|&n| (n, 0.5 * n * n.log2().powi(2)))
+            .collect();
+        let fit = Model::NLogN.fit(&points).unwrap();
+        assert!((fit.exponent - 2.0).abs() < 1e-9, "exponent {}", fit.exponent);
+        assert!((fit.predict(500.0) - 0.5 * 500.0 * 500f64.log2().powi(2)).abs() < 1e-6);
+
+        assert!(Model::Power.fit(&points[..1]).is_none(), "one point has no slope");
+    }
+
+    fn parameters(distribution: Distribution) -> SweepParameters {
+        SweepParameters {
+            experiment: Experiment {
+                length: 0,
+                seed: 5,
+                rounds: 20,
+                distribution,
+                mode: RoundMode::Independent,
+            },
+            max_distance: MaxDistance::Length,
+            quicksort: false,
+            threads: 0,
+        }
+    }
+
+    #[test]
+    fn test_insertion_sort_is_quadratic() {
+        let series = perform_sweep(
+            &parameters(Distribution::Uniform),
+            &Lengths::from_str("50..800").unwrap(),
+            &[GapSequence::Custom(vec![1]), GapSequence::Ciura2001],
+        ).unwrap();
+
+        assert_eq!(series[0].name, "1");
+        assert_eq!(series[1].points.len(), 5);
+
+        let insertion = Model::Power.fit(&series[0].comparisons()).unwrap();
+        let ciura = Model::Power.fit(&series[1].comparisons()).unwrap();
+        assert!(insertion.exponent > 1.8 && insertion.exponent < 2.1, "{}", insertion);
+        assert!(ciura.exponent < 1.5, "{}", ciura);
+    }
+
+    #[test]
+    fn test_max_distance_follows_length() {
+        let mut parameters = parameters(Distribution::BoundedSwaps {
+            max_distance: 10.0,
+            probability: 0.5,
+        });
+
+        assert!(
+            parameters.distribution(400)
+                == Distribution::BoundedSwaps { max_distance: 400.0, probability: 0.5 },
+            "resolved at each length",
+        );
+        assert!(parameters.describe().contains("distance of the length"), "{}", parameters.describe());
+
+        parameters.max_distance = MaxDistance::Custom(10.0);
+        assert!(
+            parameters.distribution(400)
+                == Distribution::BoundedSwaps { max_distance: 10.0, probability: 0.5 },
+            "a given distance is kept",
+        );
+        assert!(parameters.describe().contains("distance of 10"), "{}", parameters.describe());
+
+        let parameters = self::parameters(Distribution::Reversed);
+        assert!(parameters.distribution(400) == Distribution::Reversed);
+    }
+
+    #[test]
+    fn test_sweep_threads_and_quicksort() {
+        let mut parameters = parameters(Distribution::Uniform);
+        parameters.quicksort = true;
+        let lengths = Lengths::from_str("20..80").unwrap();
+        let sequences = [GapSequence::Ciura2001];
+
+        let sequential = perform_sweep(&parameters, &lengths, &sequences).unwrap();
+        parameters.threads = 3;
+        let parallel = perform_sweep(&parameters, &lengths, &sequences).unwrap();
+
+        assert_eq!(sequential.len(), 2, "a series for quicksort");
+        assert_eq!(sequential[1].name, "Quicksort");
+        assert!(sequential[1].points.iter().all(|point| point.moves.is_none()), "no moves");
+        assert!(sequential[1].moves().is_empty());
+
+        for (sequential, parallel) in sequential.iter().zip(parallel.iter()) {
+            assert_eq!(
+                sequential.comparisons(),
+                parallel.comparisons(),
+                "same results on any number of threads",
+            );
+            assert_eq!(sequential.moves(), parallel.moves());
+        }
+    }
+}